@@ -63,9 +63,9 @@ const MyBetsPage = () => {
         actions.cancelWager(userWagerIndex, wallet)
     }
 
-    function claimWinnings(index: number, winnings: number) {
+    function claimWinnings(index: number) {
         const userWagerIndex = userWagers.indexOf(settledBets[index])
-        actions.claimWinnings(winnings, userWagerIndex, wallet)
+        actions.claimWinnings(userWagerIndex, wallet)
     }
 
     function calculateWinnings(index: number) {
@@ -153,7 +153,7 @@ const MyBetsPage = () => {
                                                 <td className="p-4 text-center">{(wager.account.betValue.toNumber() / LAMPORTS_PER_SOL).toFixed(2)} {SOL_TOKEN_LOGO}</td>
                                                 <td className="p-4 text-center">
                                                     {isWinningBet ?
-                                                        <button className="text-green-600 border-green-600 hover:bg-green-200/20 border rounded-lg p-1.5 mt-1" onClick={() => claimWinnings(index, winnings)}>Claim {(winnings / LAMPORTS_PER_SOL).toFixed(2)} {SOL_TOKEN_LOGO}</button>
+                                                        <button className="text-green-600 border-green-600 hover:bg-green-200/20 border rounded-lg p-1.5 mt-1" onClick={() => claimWinnings(index)}>Claim {(winnings / LAMPORTS_PER_SOL).toFixed(2)} {SOL_TOKEN_LOGO}</button>
                                                         : null}
                                                 </td>
                                             </tr>
@@ -1,4 +1,4 @@
-import { BN, Program, ProgramAccount, Provider } from "@project-serum/anchor";
+import { Program, ProgramAccount, Provider } from "@project-serum/anchor";
 import { useWallet, WalletContextState } from "@solana/wallet-adapter-react";
 import { clusterApiUrl, Connection, Keypair } from "@solana/web3.js";
 import produce from "immer";
@@ -110,7 +110,7 @@ const userAccountStore = create<UserAccountState>((set, get) => {
                     failureToast('Error trying to cancel wager. Please try again.')
                 })
             },
-            async claimWinnings(winnings: number, index: number, wallet: WalletContextState) {
+            async claimWinnings(index: number, wallet: WalletContextState) {
                 const userWagers = get().userWagers
                 const userAccount = get().userAccount
                 const network = "http://127.0.0.1:8899";
@@ -123,7 +123,7 @@ const userAccountStore = create<UserAccountState>((set, get) => {
 
                 const wagerDetail = userWagers[index]
 
-                program.rpc.claimWinnings(new BN(winnings), {
+                program.rpc.claimWinnings({
                     accounts: {
                         betState: wagerDetail.account.betState,
                         wagerDetail: wagerDetail.publicKey,
@@ -0,0 +1,113 @@
+// Integer fixed-point helpers for lamport math. Rates are expressed in basis points and every
+// intermediate product is computed in u128, so nothing here touches floating point.
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+    Nearest,
+}
+
+// value * numerator / denominator with an explicit rounding mode, None on division by zero or
+// when the result does not fit back into a u64
+pub fn mul_div(value: u64, numerator: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+
+    let product = value as u128 * numerator as u128;
+    let denominator = denominator as u128;
+    let quotient = product / denominator;
+    let remainder = product % denominator;
+
+    let rounded = match rounding {
+        Rounding::Down => quotient,
+        Rounding::Up if remainder > 0 => quotient + 1,
+        Rounding::Up => quotient,
+        Rounding::Nearest if remainder * 2 >= denominator => quotient + 1,
+        Rounding::Nearest => quotient,
+    };
+
+    if rounded > u64::MAX as u128 {
+        None
+    } else {
+        Some(rounded as u64)
+    }
+}
+
+// amount * bps / 10_000
+pub fn apply_bps(amount: u64, bps: u16, rounding: Rounding) -> Option<u64> {
+    mul_div(amount, bps as u64, BPS_DENOMINATOR, rounding)
+}
+
+// Splits an amount that already includes a fee of fee_bps on top of the net value into (net, fee).
+// The net value rounds down so any rounding lamport goes to the fee, and net + fee always equals gross.
+pub fn split_fee_inclusive(gross: u64, fee_bps: u16) -> Option<(u64, u64)> {
+    let net = mul_div(gross, BPS_DENOMINATOR, BPS_DENOMINATOR + fee_bps as u64, Rounding::Down)?;
+    Some((net, gross - net))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounding_modes() {
+        assert_eq!(mul_div(10, 1, 3, Rounding::Down), Some(3));
+        assert_eq!(mul_div(10, 1, 3, Rounding::Up), Some(4));
+        assert_eq!(mul_div(10, 1, 3, Rounding::Nearest), Some(3));
+        assert_eq!(mul_div(10, 1, 4, Rounding::Nearest), Some(3));
+        assert_eq!(mul_div(9, 1, 3, Rounding::Up), Some(3));
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn mul_div_at_u64_max() {
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down), Some(u64::MAX));
+        assert_eq!(mul_div(u64::MAX, 2, 2, Rounding::Up), Some(u64::MAX));
+        assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+        assert_eq!(mul_div(u64::MAX, u64::MAX - 1, u64::MAX, Rounding::Down), Some(u64::MAX - 1));
+        assert_eq!(mul_div(u64::MAX, u64::MAX - 1, u64::MAX, Rounding::Up), Some(u64::MAX - 1));
+    }
+
+    #[test]
+    fn apply_bps_one_lamport() {
+        assert_eq!(apply_bps(1, 200, Rounding::Down), Some(0));
+        assert_eq!(apply_bps(1, 200, Rounding::Up), Some(1));
+        assert_eq!(apply_bps(1, 200, Rounding::Nearest), Some(0));
+        assert_eq!(apply_bps(1, 10_000, Rounding::Down), Some(1));
+    }
+
+    #[test]
+    fn apply_bps_at_u64_max() {
+        assert_eq!(apply_bps(u64::MAX, 10_000, Rounding::Down), Some(u64::MAX));
+        assert_eq!(apply_bps(u64::MAX, 0, Rounding::Up), Some(0));
+        assert_eq!(apply_bps(u64::MAX, 200, Rounding::Down), Some(368_934_881_474_191_032));
+        assert_eq!(apply_bps(u64::MAX, 200, Rounding::Up), Some(368_934_881_474_191_033));
+        assert_eq!(apply_bps(u64::MAX, u16::MAX, Rounding::Down), None);
+    }
+
+    #[test]
+    fn split_fee_inclusive_matches_take_rate() {
+        assert_eq!(split_fee_inclusive(510_000_000, 200), Some((500_000_000, 10_000_000)));
+        assert_eq!(split_fee_inclusive(102, 200), Some((100, 2)));
+        assert_eq!(split_fee_inclusive(0, 200), Some((0, 0)));
+    }
+
+    #[test]
+    fn split_fee_inclusive_one_lamport_goes_to_fee() {
+        assert_eq!(split_fee_inclusive(1, 200), Some((0, 1)));
+        assert_eq!(split_fee_inclusive(1, 0), Some((1, 0)));
+    }
+
+    #[test]
+    fn split_fee_inclusive_at_u64_max() {
+        let (net, fee) = split_fee_inclusive(u64::MAX, 200).unwrap();
+
+        assert_eq!(net, 18_085_043_209_519_168_250);
+        assert_eq!(net + fee, u64::MAX);
+        assert_eq!(split_fee_inclusive(u64::MAX, 0), Some((u64::MAX, 0)));
+    }
+}
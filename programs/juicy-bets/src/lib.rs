@@ -1,14 +1,31 @@
+use std::cmp::Ordering;
+
 use anchor_lang::prelude::*;
 use anchor_lang::prelude::program;
 use anchor_lang::prelude::Rent;
+use anchor_lang::AccountsClose;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::system_instruction;
 use anchor_lang::solana_program::system_program;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+
+pub mod fixed_point;
+pub mod oracle;
+#[cfg(test)]
+mod tests;
+
+use fixed_point::Rounding;
+use oracle::OraclePrice;
 
 declare_id!("CYpwqMgesShNFYrkLzpHC3NmaWqAXkiRZihAgekb535w");
 
-// TODO:
-// Figure out where to put Juiced Dev take rate: 2%
+// Bond the resolver posts with a proposed outcome and a disputer posts to challenge it: 0.1 SOL
+const OUTCOME_BOND_LAMPORTS: u64 = 100_000_000;
 
-const TAKE_RATE: f64  = 1.02;
+// How long a settled bet state stays readable for history before its creator can close it: 30 days
+const SETTLED_BET_RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
 
 
 #[program]
@@ -18,7 +35,8 @@ pub mod juicy_bets {
     // *** Betting Functionality *** //
 
     // TODO: Bet state should be initialized every market close 
-    // Endpoint that will initialize a bet state
+    // Endpoint that will initialize a bet state settled in lamports
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_bet_state(
         ctx: Context<InitializeBetState>,
         start: u64,
@@ -26,36 +44,68 @@ pub mod juicy_bets {
         symbol: String,
         snapshot_price: u128,
         bet_range: u8,
+        resolver: Pubkey,
+        admin: Pubkey,
+        claim_window: u64,
+        dispute_window: u64,
     ) -> Result<()> {
-        let bet_state = &mut ctx.accounts.bet_state;
-        let bet_creator = &mut ctx.accounts.bet_creator;
-
-        bet_state.symbol = symbol;
-        bet_state.creator = bet_creator.key();
-        bet_state.running_total_pool = 0;
-        bet_state.static_total_pool = 0;
-        bet_state.party_one_pool = 0;
-        bet_state.party_two_pool = 0;
-        bet_state.bet_outcome = BetOutcomeStatus::Undecided;
-        bet_state.status = BetStateStatus::Open;
-        bet_state.start_time = start;
-        bet_state.end_time = start + duration;
-
-        bet_state.snapshot_price = snapshot_price;
-
-        match bet_range {
-            0 => { bet_state.bet_range = BetRange::NegativeThreeAndOver },
-            1 => { bet_state.bet_range = BetRange::NegativeTwoToThree },
-            2 => { bet_state.bet_range = BetRange::NegativeOneToTwo },
-            3 => { bet_state.bet_range = BetRange::NegativeOneToZero },
-            4 => { bet_state.bet_range = BetRange::ZeroToPositiveOne },
-            5 => { bet_state.bet_range = BetRange::PositiveOneToTwo },
-            6 => { bet_state.bet_range = BetRange::PositiveTwoToThree },
-            7 => { bet_state.bet_range = BetRange::PositiveThreeAndOver },
-            _ => { return Err(error!(ErrorCode::InvalidBetRange)); }
-        };
 
-        Ok(())
+        open_lamport_vault(&ctx.accounts.bet_vault, &ctx.accounts.bet_creator, &ctx.accounts.system_program)?;
+
+        initialize_market(
+            &mut ctx.accounts.bet_state,
+            *ctx.bumps.get("bet_state").unwrap(),
+            &ctx.accounts.bet_creator,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.config,
+            Pubkey::default(),
+            start,
+            duration,
+            symbol,
+            snapshot_price,
+            bet_range,
+            resolver,
+            admin,
+            claim_window,
+            dispute_window,
+        )
+    }
+
+    // Endpoint that will initialize a bet state settled in an SPL token, along with the market vault holding
+    // its pools. The bet's lamport vault still holds the outcome bonds.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_token_bet_state(
+        ctx: Context<InitializeTokenBetState>,
+        start: u64,
+        duration: u64,
+        symbol: String,
+        snapshot_price: u128,
+        bet_range: u8,
+        resolver: Pubkey,
+        admin: Pubkey,
+        claim_window: u64,
+        dispute_window: u64,
+    ) -> Result<()> {
+
+        open_lamport_vault(&ctx.accounts.bet_vault, &ctx.accounts.bet_creator, &ctx.accounts.system_program)?;
+
+        initialize_market(
+            &mut ctx.accounts.bet_state,
+            *ctx.bumps.get("bet_state").unwrap(),
+            &ctx.accounts.bet_creator,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.config,
+            ctx.accounts.collateral_mint.key(),
+            start,
+            duration,
+            symbol,
+            snapshot_price,
+            bet_range,
+            resolver,
+            admin,
+            claim_window,
+            dispute_window,
+        )
     }
 
     // Endpoint that allows a user to place a wager on a bet state
@@ -68,47 +118,68 @@ pub mod juicy_bets {
         let bet_state = &mut ctx.accounts.bet_state;
         let wager_detail = &mut ctx.accounts.wager_detail;
         let user_account = &mut ctx.accounts.user_account;
-        let bettor = &mut ctx.accounts.bettor_account;
-        // let bet_creator = &mut ctx.accounts.bet_creator;
+        let bettor = &ctx.accounts.bettor_account;
+        let treasury = &mut ctx.accounts.treasury;
+        let config = &ctx.accounts.config;
+        let account_vault = &ctx.accounts.account_vault;
+        let account_vault_bump = *ctx.bumps.get("account_vault").unwrap();
+        let system_program = &ctx.accounts.system_program;
 
-        // separate take rate from actual wager
-        let wager_amount_float = lamports as f64 / TAKE_RATE;
-        let wager_amount_int = wager_amount_float as u64;
+        if lamports < config.min_wager {
+            return Err(error!(ErrorCode::WagerBelowMinimum));
+        }
 
-        // grab the take rate from actual wager
-        let take_rate_amount_int = lamports - wager_amount_int;
-        
-        // add lamport amount to the static total pool and running_total_pool
-        bet_state.static_total_pool += wager_amount_int;
-        bet_state.running_total_pool += wager_amount_int;
-        
-        // update the BetterDetail obj
-        wager_detail.bet_value = wager_amount_int;
-        wager_detail.bet_state = bet_state.key();
-        wager_detail.bettor = bettor.key();
-
-        // based on which party the bet is placed, assign correct PartyStatus and correct amount of lamports to correct party on BetState type
-        if party == 1 {
-            wager_detail.party = PartyStatus::PartyOne;
-            bet_state.party_one_pool += wager_amount_int;
-        } else if party == 2 {
-            wager_detail.party = PartyStatus::PartyTwo;
-            bet_state.party_two_pool += wager_amount_int;
-        } else {
-            return Err(error!(ErrorCode::InvalidParty));
+        if lamports > config.max_wager {
+            return Err(error!(ErrorCode::WagerAboveMaximum));
+        }
+
+        bet_state.check_betting_window(Clock::get()?.unix_timestamp)?;
+
+        // separate take rate from actual wager, the rounding lamport goes to the take rate
+        let (wager_amount_int, take_rate_amount_int) = fixed_point::split_fee_inclusive(lamports, config.fee_bps)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // A wager worth nothing after the fee could never be told apart from an empty pool when refunding
+        if wager_amount_int == 0 {
+            return Err(error!(ErrorCode::WagerTooSmall));
         }
         
-        // add the bet to the user account
-        user_account.active_wagers.push(wager_detail.key());
-        user_account.current_balance -= lamports;
+        record_wager(
+            bet_state,
+            wager_detail,
+            user_account,
+            party,
+            wager_amount_int,
+            *ctx.bumps.get("wager_detail").unwrap(),
+            bettor,
+            system_program,
+        )?;
+
+        user_account.current_balance = user_account.current_balance.checked_sub(lamports).ok_or(ErrorCode::InsufficientAccountBalance)?;
 
-        // Transfer actual wager lamport amount from user account into bet state wallet
-        **user_account.to_account_info().try_borrow_mut_lamports()? = user_account.to_account_info().lamports().checked_sub(wager_amount_int).ok_or(ProgramError::InvalidArgument)?;
-        **bet_state.to_account_info().try_borrow_mut_lamports()? = bet_state.to_account_info().lamports().checked_add(wager_amount_int).ok_or(ProgramError::InvalidArgument)?;
+        // record the take fee against the treasury and the bet
+        treasury.total_fees_collected = treasury.total_fees_collected.checked_add(take_rate_amount_int).ok_or(ErrorCode::MathOverflow)?;
+        bet_state.total_fees = bet_state.total_fees.checked_add(take_rate_amount_int).ok_or(ErrorCode::MathOverflow)?;
 
-        // Transfer take fee lamport amount from user account into bet creator wallet
-        // **user_account.to_account_info().try_borrow_mut_lamports()? = user_account.to_account_info().lamports().checked_sub(take_rate_amount_int).ok_or(ProgramError::InvalidArgument)?;
-        // **bet_creator.to_account_info().try_borrow_mut_lamports()? = bet_creator.to_account_info().lamports().checked_add(take_rate_amount_int).ok_or(ProgramError::InvalidArgument)?;
+        // Transfer actual wager lamport amount from the user's vault into the bet's vault
+        transfer_from_account_vault(user_account, account_vault, account_vault_bump, &ctx.accounts.bet_vault, system_program, wager_amount_int)?;
+
+        // Transfer take fee lamport amount from the user's vault into the treasury
+        transfer_from_account_vault(user_account, account_vault, account_vault_bump, &treasury.to_account_info(), system_program, take_rate_amount_int)?;
+
+        emit!(WagerPlaced {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: bettor.key(),
+            party: wager_detail.party,
+            amount: lamports,
+            fee: take_rate_amount_int,
+            bet_value: wager_detail.bet_value,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
@@ -121,425 +192,3572 @@ pub mod juicy_bets {
         let wager_detail = &mut ctx.accounts.wager_detail;
         let user_account = &mut ctx.accounts.user_account;
 
-        // Grab bettor details bet value
-        let bet_value_from_wager_detail = wager_detail.bet_value;
-
-        // Subtract the user's bet amount from the total pool
-        bet_state.static_total_pool -= bet_value_from_wager_detail;
-        bet_state.running_total_pool -= bet_value_from_wager_detail;
-        
-        // Subtract the user's bet amount from the respective party's pool
-        match wager_detail.party {
-            PartyStatus::PartyOne => bet_state.party_one_pool -= bet_value_from_wager_detail,
-            PartyStatus::PartyTwo => bet_state.party_two_pool -= bet_value_from_wager_detail
-        }
+        bet_state.check_betting_window(Clock::get()?.unix_timestamp)?;
 
-        // iterate through the user accounts bets and find the matching bet
-        if let Some(keypos) = user_account.active_wagers.iter().position(|x| *x == wager_detail.key()) {
-            user_account.active_wagers.remove(keypos);
-        } else {
-            return Err(error!(ErrorCode::ActiveWagerNotFound));
-        }
+        let bet_value_from_wager_detail = remove_cancelled_wager(bet_state, wager_detail, user_account)?;
 
         // Add back to user account balance
-        user_account.current_balance += bet_value_from_wager_detail as u64;
+        user_account.current_balance += bet_value_from_wager_detail;
+
+        transfer_from_bet_vault(
+            bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            &ctx.accounts.account_vault,
+            &ctx.accounts.system_program,
+            bet_value_from_wager_detail,
+        )?;
 
-        **bet_state.to_account_info().try_borrow_mut_lamports()? = bet_state.to_account_info().lamports().checked_sub(bet_value_from_wager_detail as u64).ok_or(ProgramError::InvalidArgument)?;
-        **user_account.to_account_info().try_borrow_mut_lamports()? = user_account.to_account_info().lamports().checked_add(bet_value_from_wager_detail as u64).ok_or(ProgramError::InvalidArgument)?;
+        emit!(WagerCancelled {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: wager_detail.bettor,
+            party: wager_detail.party,
+            amount: bet_value_from_wager_detail,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(()) 
     }
 
     // Endpoint that allows a user to claim their winnings from a bet state given that the bet is closed and a winner is decided
-    pub fn claim_winnings(ctx:Context<ClaimWinnings>, winnings_amount: u64) -> Result<()> { 
+    pub fn claim_winnings(ctx:Context<ClaimWinnings>) -> Result<()> { 
 
         let bet_state = &mut ctx.accounts.bet_state;
         let wager_detail = &mut ctx. accounts.wager_detail;
         let user_account = &mut ctx.accounts.user_account;
-        
-        // Make the lamport credit and debit
-        if bet_state.bet_outcome == BetOutcomeStatus::PartyOneWin && wager_detail.party == PartyStatus::PartyOne {
-            // Transfer winnings amount from bet state to bettor
-            **bet_state.to_account_info().try_borrow_mut_lamports()? = bet_state.to_account_info().lamports().checked_sub(winnings_amount).ok_or(ProgramError::InvalidArgument)?;
-            **user_account.to_account_info().try_borrow_mut_lamports()? = user_account.to_account_info().lamports().checked_add(winnings_amount).ok_or(ProgramError::InvalidArgument)?;
-            bet_state.running_total_pool -= winnings_amount;
-        } else if bet_state.bet_outcome == BetOutcomeStatus::PartyTwoWin && wager_detail.party == PartyStatus::PartyTwo {
-            // Transfer winnings amount from bet state to bettor
-            **bet_state.to_account_info().try_borrow_mut_lamports()? = bet_state.to_account_info().lamports().checked_sub(winnings_amount).ok_or(ProgramError::InvalidArgument)?;
-            **user_account.to_account_info().try_borrow_mut_lamports()? = user_account.to_account_info().lamports().checked_add(winnings_amount).ok_or(ProgramError::InvalidArgument)?;
-            bet_state.running_total_pool -= winnings_amount;
-        } else {
-            return Err(error!(ErrorCode::NonWinningParty));
-        }
 
-        // iterate through the user accounts bets and find the matching bet
-        // TODO: Test this works
-        if let Some(keypos) = user_account.active_wagers.iter().position(|x| *x == wager_detail.key()) {
-            user_account.active_wagers.remove(keypos);
-        } else {
-            return Err(error!(ErrorCode::ActiveWagerNotFound));
-        }
+        bet_state.check_claim_window(&ctx.accounts.config, Clock::get()?.unix_timestamp)?;
+
+        let winnings_amount = record_winning_claim(bet_state, wager_detail, user_account)?;
+
+        // Transfer winnings amount from the bet's vault to the bettor's vault
+        transfer_from_bet_vault(
+            bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            &ctx.accounts.account_vault,
+            &ctx.accounts.system_program,
+            winnings_amount,
+        )?;
 
         // Add the winning lamports to the user's account balance
         user_account.current_balance += winnings_amount;
 
+        emit!(WinningsClaimed {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: wager_detail.bettor,
+            bet_value: wager_detail.bet_value,
+            amount: winnings_amount,
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that settles a losing wager once the bet is decided, before or after the bet is settled. Callable
+    // by the bettor or a crank, the wager's rent always goes back to the bettor.
+    pub fn acknowledge_loss(ctx:Context<AcknowledgeLoss>) -> Result<()> {
+
+        let bet_state = &mut ctx.accounts.bet_state;
+        let wager_detail = &ctx.accounts.wager_detail;
+        let user_account = &mut ctx.accounts.user_account;
+
+        // Only wagers on the losing side can be acknowledged as a loss, or a winning wager whose payout was
+        // swept into the treasury because it was never claimed
+        if bet_state.winning_party() == Some(wager_detail.party) && bet_state.unclaimed_swept == 0 {
+            return Err(error!(ErrorCode::NonLosingParty));
+        }
+
+        bet_state.close_position()?;
+        user_account.remove_active_wager(wager_detail.key())?;
+
+        user_account.losses += 1;
+        user_account.realized_profit = user_account.realized_profit
+            .checked_sub(wager_detail.bet_value as i64)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(LossAcknowledged {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: wager_detail.bettor,
+            bet_value: wager_detail.bet_value,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that refunds a wager's full bet value from a voided bet into the bettor's user account.
+    // The take fee paid on the wager stays in the treasury. Voided bets are never swept, so refunds have no
+    // deadline.
+    pub fn refund_wager(ctx:Context<RefundWager>) -> Result<()> {
+
+        refund_wager_into_user_account(
+            &mut ctx.accounts.bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            &ctx.accounts.wager_detail,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.account_vault,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit_wager_refunded(&ctx.accounts.bet_state, &ctx.accounts.wager_detail)
+    }
+
+    // Permissionless crank that refunds wagers from a voided or cancelled bet. remaining_accounts holds
+    // (wager_detail, user_account, account_vault, bettor) quadruples; once every wager is refunded the bet
+    // state is closed and its rent and its vault's rent go back to the creator.
+    pub fn refund_wagers<'info>(ctx: Context<'_, '_, '_, 'info, RefundWagers<'info>>) -> Result<()> {
+
+        let bet_vault_bump = *ctx.bumps.get("bet_vault").unwrap();
+        let wagers = ctx.remaining_accounts.chunks_exact(4);
+
+        if !wagers.remainder().is_empty() {
+            return Err(error!(ErrorCode::InvalidRemainingAccounts));
+        }
+
+        for accounts in wagers {
+            let wager_detail: Account<WagerDetail> = Account::try_from(&accounts[0])?;
+            let mut user_account: Account<UserAccount> = Account::try_from(&accounts[1])?;
+            let account_vault = &accounts[2];
+            let bettor = &accounts[3];
+
+            if wager_detail.bet_state != ctx.accounts.bet_state.key() {
+                return Err(error!(ErrorCode::WagerBetStateMismatch));
+            }
+
+            if wager_detail.bettor != bettor.key() {
+                return Err(error!(ErrorCode::WagerBettorMismatch));
+            }
+
+            if user_account.account_owner != bettor.key() {
+                return Err(error!(ErrorCode::NotAccountOwnerToRefundWager));
+            }
+
+            let (account_vault_key, _) = Pubkey::find_program_address(
+                &[b"account_vault", user_account.key().as_ref()],
+                ctx.program_id,
+            );
+
+            if account_vault.key() != account_vault_key {
+                return Err(error!(ErrorCode::InvalidVault));
+            }
+
+            refund_wager_into_user_account(
+                &mut ctx.accounts.bet_state,
+                &ctx.accounts.bet_vault,
+                bet_vault_bump,
+                &wager_detail,
+                &mut user_account,
+                account_vault,
+                &ctx.accounts.system_program,
+            )?;
+
+            emit_wager_refunded(&ctx.accounts.bet_state, &wager_detail)?;
+
+            // Persist the user account before the next quadruple, which may belong to the same bettor
+            user_account.exit(ctx.program_id)?;
+            wager_detail.close(bettor.clone())?;
+        }
+
+        if ctx.accounts.bet_state.running_total_pool == 0 {
+            close_bet_vault(
+                &ctx.accounts.bet_state,
+                &ctx.accounts.bet_vault,
+                bet_vault_bump,
+                &ctx.accounts.bet_creator,
+                &ctx.accounts.system_program,
+            )?;
+            ctx.accounts.bet_state.close(ctx.accounts.bet_creator.to_account_info())?;
+        }
+
         Ok(())
     }
 
     // Endpoint that allows the app to close a bet once the betting time (end date - start date) has elapsed
-    pub fn close_bet_state(
-        ctx:Context<CloseBetState>, 
-        end: u64
-    ) -> Result<()> { 
+    pub fn close_bet_state(ctx:Context<CloseBetState>) -> Result<()> { 
 
         let bet_state = &mut ctx.accounts.bet_state;
 
-        bet_state.end_time = end;
+        if Clock::get()?.unix_timestamp < bet_state.end_time as i64 {
+            return Err(error!(ErrorCode::BetNotEnded));
+        }
+
         bet_state.status = BetStateStatus::Closed;
 
+        emit!(BetClosed {
+            bet_state: bet_state.key(),
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            static_total_pool: bet_state.static_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(()) 
     }
 
-    // Endpoint that allows a user to cancel a bet state they created
+    // Endpoint that allows a user to cancel a bet state they created, up until its end time. Past it the
+    // outcome may already be known, so the bet has to be resolved or voided through a proposal instead.
     pub fn cancel_bet_state(ctx:Context<CancelBetState>) -> Result<()> { 
 
         let bet_state = &mut ctx.accounts.bet_state;
 
-        if bet_state.running_total_pool != 0 {
-            return Err(error!(ErrorCode::FundsStillInPlay))
+        if Clock::get()?.unix_timestamp >= bet_state.end_time as i64 {
+            return Err(error!(ErrorCode::BettingWindowEnded));
         }
 
-        // Do we wanna only close the bet if there aren't any active bets except the initiating bettor's?
+        // Every wager becomes refundable, through refund_wager or the refund_wagers crank
+        bet_state.status = BetStateStatus::Cancelled;
+        bet_state.finalize_outcome(BetOutcomeStatus::Void, &ctx.accounts.config, Clock::get()?.unix_timestamp)?;
+
+        emit!(BetCancelled {
+            bet_state: bet_state.key(),
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(()) 
     }
 
-    // Endpoint that will determine who won the bet, either Party 1 or Party 2, mutating the bet_outcome field
-    pub fn decide_bet_state_outcome(ctx:Context<DecideBetState>, outcome: u8) -> Result<()> { 
+    // Endpoint for the resolver to propose who won the bet, either Party 1, Party 2 or Void, backed by a bond.
+    // The outcome only takes effect once it is finalized after the dispute window or arbitrated.
+    pub fn propose_bet_state_outcome(ctx:Context<ProposeBetStateOutcome>, outcome: u8) -> Result<()> { 
 
         let bet_state = &mut ctx.accounts.bet_state;
+        let resolver = &ctx.accounts.resolver;
 
-        match outcome {
-            1 => { bet_state.bet_outcome = BetOutcomeStatus::PartyOneWin },
-            2 => { bet_state.bet_outcome = BetOutcomeStatus::PartyTwoWin },
-            _ => { return Err(error!(ErrorCode::InvalidBetOutcome)); }
-        };
+        bet_state.proposed_outcome = BetOutcomeStatus::from_proposal(outcome)?;
+
+        invoke(
+            &system_instruction::transfer(&resolver.key(), &ctx.accounts.bet_vault.key(), OUTCOME_BOND_LAMPORTS),
+            &[
+                resolver.to_account_info(),
+                ctx.accounts.bet_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        bet_state.resolution = OutcomeResolution::Proposed;
+        bet_state.proposer = resolver.key();
+        bet_state.proposed_at = Clock::get()?.unix_timestamp;
+
+        emit!(OutcomeProposed {
+            bet_state: bet_state.key(),
+            proposer: bet_state.proposer,
+            outcome: bet_state.proposed_outcome,
+            bond: OUTCOME_BOND_LAMPORTS,
+            timestamp: bet_state.proposed_at,
+        });
 
         Ok(()) 
     }
 
-    // Endpoint that will put the bet state into a "settled" state, all winnings have been claimed and the bet state account is ready to be closed
-    pub fn settle_bet_state(ctx: Context<SettleBetState>) -> Result<()> {
+    // Endpoint for anyone to dispute a proposed outcome within the dispute window by posting a matching bond
+    pub fn dispute_bet_state_outcome(ctx:Context<DisputeBetStateOutcome>) -> Result<()> {
 
         let bet_state = &mut ctx.accounts.bet_state;
+        let disputer = &ctx.accounts.disputer;
 
-        if bet_state.running_total_pool != 0 {
-            return Err(error!(ErrorCode::FundsStillInPlay));
+        if Clock::get()?.unix_timestamp > bet_state.dispute_deadline()? {
+            return Err(error!(ErrorCode::DisputeWindowClosed));
         }
 
+        invoke(
+            &system_instruction::transfer(&disputer.key(), &ctx.accounts.bet_vault.key(), OUTCOME_BOND_LAMPORTS),
+            &[
+                disputer.to_account_info(),
+                ctx.accounts.bet_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        bet_state.resolution = OutcomeResolution::Disputed;
+        bet_state.disputer = disputer.key();
+
+        emit!(OutcomeDisputed {
+            bet_state: bet_state.key(),
+            disputer: bet_state.disputer,
+            bond: OUTCOME_BOND_LAMPORTS,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
+    // Permissionless endpoint that finalizes an undisputed proposal once the dispute window has passed and
+    // returns the proposer's bond
+    pub fn finalize_bet_state_outcome(ctx:Context<FinalizeBetStateOutcome>) -> Result<()> {
 
-    // *** User Account Functionality *** //
-    pub fn initialize_user_account(ctx: Context<InitializeUserAccount>) -> Result<()> {
+        let bet_state = &mut ctx.accounts.bet_state;
+        let proposer = &ctx.accounts.proposer;
 
-        let user_account = &mut ctx.accounts.user_account;
-        let account_owner = &mut ctx.accounts.account_owner;
+        if Clock::get()?.unix_timestamp <= bet_state.dispute_deadline()? {
+            return Err(error!(ErrorCode::DisputeWindowStillOpen));
+        }
 
-        user_account.account_owner = account_owner.key();
-        user_account.wins = 0;
-        user_account.losses = 0;
-        user_account.current_balance = 0;
+        transfer_from_bet_vault(
+            bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            proposer,
+            &ctx.accounts.system_program,
+            OUTCOME_BOND_LAMPORTS,
+        )?;
 
-        Ok(())
-    }
+        let outcome = bet_state.proposed_outcome;
+        bet_state.finalize_outcome(outcome, &ctx.accounts.config, Clock::get()?.unix_timestamp)?;
+
+        emit!(OutcomeDecided {
+            bet_state: bet_state.key(),
+            outcome: bet_state.bet_outcome,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            claim_deadline: bet_state.claim_deadline,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    pub fn close_user_account(_ctx: Context<CloseUserAccount>) -> Result<()> {
         Ok(())
     }
 
-    pub fn deposit_into_account(ctx: Context<DepositIntoAccount>, lamports: u64) -> Result<()> {
+    // Endpoint for the arbitrator to settle a disputed proposal. Whoever the arbitrator sides with, the
+    // proposer or the disputer, receives both bonds.
+    pub fn arbitrate_bet_state_outcome(ctx:Context<ArbitrateBetStateOutcome>, outcome: u8) -> Result<()> {
 
-        let user_account = &mut ctx.accounts.user_account;
+        let bet_state = &mut ctx.accounts.bet_state;
+        let outcome = BetOutcomeStatus::from_proposal(outcome)?;
+        let bonds = OUTCOME_BOND_LAMPORTS * 2;
 
-        user_account.current_balance += lamports;
+        let bond_recipient = if outcome == bet_state.proposed_outcome {
+            ctx.accounts.proposer.to_account_info()
+        } else {
+            ctx.accounts.disputer.to_account_info()
+        };
+
+        transfer_from_bet_vault(
+            bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            &bond_recipient,
+            &ctx.accounts.system_program,
+            bonds,
+        )?;
+
+        bet_state.finalize_outcome(outcome, &ctx.accounts.config, Clock::get()?.unix_timestamp)?;
+
+        emit!(OutcomeDecided {
+            bet_state: bet_state.key(),
+            outcome: bet_state.bet_outcome,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            claim_deadline: bet_state.claim_deadline,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
 
-    // TODO: Test this function
-    pub fn withdraw_from_account(ctx: Context<WithdrawFromAccount>, lamports: u64) -> Result<()> {
+    // Endpoint that allows the bet's admin to hand the resolver and admin roles to new keys, the arbitrator comes
+    // from the config and can't be reassigned by the bet's admin
+    pub fn set_bet_state_authorities(ctx: Context<SetBetStateAuthorities>, resolver: Pubkey, admin: Pubkey) -> Result<()> {
 
-        let user_account = &mut ctx.accounts.user_account;
-        let account_owner = &mut ctx.accounts.account_owner;
+        let bet_state = &mut ctx.accounts.bet_state;
 
-        user_account.current_balance.checked_sub(lamports).ok_or(ErrorCode::InvalidWithdrawalAmount)?;
+        if resolver == bet_state.arbitrator {
+            return Err(error!(ErrorCode::ArbitratorNotIndependent));
+        }
 
-        user_account.current_balance -= lamports;
+        bet_state.resolver = resolver;
+        bet_state.admin = admin;
 
-        **user_account.to_account_info().try_borrow_mut_lamports()? = user_account.to_account_info().lamports().checked_sub(lamports).ok_or(ProgramError::InvalidArgument)?;
-        **account_owner.to_account_info().try_borrow_mut_lamports()? = account_owner.to_account_info().lamports().checked_add(lamports).ok_or(ProgramError::InvalidArgument)?;
+        emit!(BetAuthoritiesUpdated {
+            bet_state: bet_state.key(),
+            resolver: bet_state.resolver,
+            admin: bet_state.admin,
+            arbitrator: bet_state.arbitrator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
 
-}
+    // Endpoint that will determine who won the bet from the market's Pyth price feed once the market has ended.
+    // A bet its creator never closed is closed here, otherwise they could hold resolution off until the feed has
+    // moved past the resolution window.
+    pub fn resolve_bet_state_outcome(ctx: Context<ResolveBetState>) -> Result<()> {
 
+        let bet_state = &mut ctx.accounts.bet_state;
 
-// ***** BETTING FUNCTIONALITY CONTEXT AND STRUCTS ***** //
-// Adding an account on a context simply means its public key should be provided when sending the instruction
+        if bet_state.status == BetStateStatus::Open {
+            if Clock::get()?.unix_timestamp < bet_state.end_time as i64 {
+                return Err(error!(ErrorCode::BetNotEnded));
+            }
 
-// Context to initialize the bet state
-#[derive(Accounts)]
-pub struct InitializeBetState<'info> {
+            bet_state.status = BetStateStatus::Closed;
 
-    #[account(init, payer = bet_creator, space = BetState::MAX_SIZE + 8)]
-    bet_state: Account<'info, BetState>,
+            emit!(BetClosed {
+                bet_state: bet_state.key(),
+                party_one_pool: bet_state.party_one_pool,
+                party_two_pool: bet_state.party_two_pool,
+                static_total_pool: bet_state.static_total_pool,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
 
-    /// CHECK: Used to pay for the bet state account
-    #[account(signer, mut)]
-    bet_creator: AccountInfo<'info>,
+        // Without wagers on both sides there is nobody to pay out against, so every wager is refunded
+        let outcome = if bet_state.party_one_pool == 0 || bet_state.party_two_pool == 0 {
+            BetOutcomeStatus::Void
+        } else {
+            let oracle_price = OraclePrice::from_account_info(&ctx.accounts.price_feed)?;
 
-    /// CHECK: Not read from or written to
-    #[account(address = system_program::ID)]
-    pub system_program: AccountInfo<'info>,
+            oracle::resolve_outcome(
+                bet_state.bet_range,
+                bet_state.snapshot_price,
+                bet_state.snapshot_expo,
+                bet_state.end_time,
+                &oracle_price,
+            )?
+        };
 
-}
+        // The oracle price is objective, so its outcome is final without going through a proposal
+        bet_state.finalize_outcome(outcome, &ctx.accounts.config, Clock::get()?.unix_timestamp)?;
 
-// Context to place a wager on a created bet 
-#[derive(Accounts)]
-pub struct PlaceWager<'info> {
+        emit!(OutcomeDecided {
+            bet_state: bet_state.key(),
+            outcome: bet_state.bet_outcome,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            claim_deadline: bet_state.claim_deadline,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    #[account(
-        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
-        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
-        mut
-    )]
-    bet_state: Account<'info, BetState>,
+        Ok(())
+    }
 
-    #[account(init, payer = bettor_account, space = WagerDetail::MAX_SIZE + 8)]
-    wager_detail: Account<'info, WagerDetail>,
+    // Endpoint that will put the bet state into a "settled" state once all winnings have been claimed or refunded.
+    // The account and its final accounting stay readable until close_settled_bet_state.
+    pub fn settle_bet_state(ctx: Context<SettleBetState>) -> Result<()> {
 
-    #[account(
-        mut,
-        constraint = user_account.account_owner == bettor_account.key() @ ErrorCode::NotAccountOwnerToPlaceWager,
-    )]
-    user_account: Account<'info, UserAccount>,
+        let bet_state = &mut ctx.accounts.bet_state;
 
-    /// CHECK: Used to pay for the wager detail account
-    #[account(signer, mut)]
-    bettor_account: AccountInfo<'info>,
+        if bet_state.running_total_pool != 0 {
+            return Err(error!(ErrorCode::FundsStillInPlay));
+        }
 
-    /// CHECK: Used to credit bet manager wallet with take rate
-    // #[account(mut)]
-    // bet_creator: AccountInfo<'info>,
+        bet_state.status = BetStateStatus::Settled;
+        bet_state.settled_at = Clock::get()?.unix_timestamp;
 
-    /// CHECK: Not read from or written to
-    #[account(address = system_program::ID)]
-    system_program: AccountInfo<'info>,
-}
+        emit!(BetSettled {
+            bet_state: bet_state.key(),
+            total_paid_out: bet_state.total_paid_out,
+            total_fees: bet_state.total_fees,
+            rounding_dust: bet_state.rounding_dust,
+            unclaimed_swept: bet_state.unclaimed_swept,
+            timestamp: bet_state.settled_at,
+        });
 
-// Context to cancel a wager on a created bet
-#[derive(Accounts)]
-pub struct CancelWager<'info> {
+        Ok(())
+    }
 
-    #[account(
-        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
-        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
-        mut,
-    )]
-    bet_state: Account<'info, BetState>,
+    // Endpoint that moves whatever nobody claimed before the claim deadline into the treasury and settles
+    // the bet. Late claimers get a ClaimWindowExpired error.
+    pub fn sweep_unclaimed_funds(ctx: Context<SweepUnclaimedFunds>) -> Result<()> {
 
-    #[account(
-        constraint = wager_detail.bettor == bettor.key(),
-        mut, 
-        close = bettor,
-    )]
-    wager_detail: Account<'info, WagerDetail>,
+        let bet_state = &mut ctx.accounts.bet_state;
+        let treasury = &mut ctx.accounts.treasury;
 
-    #[account(
-        mut,
-        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToPlaceWager,
-        constraint = user_account.active_wagers.len() > 0 @ ErrorCode::ActiveWagersEmpty,
-    )]
-    user_account: Account<'info, UserAccount>,
+        let unclaimed_amount = bet_state.sweep_unclaimed(&ctx.accounts.config, Clock::get()?.unix_timestamp)?;
 
-    /// CHECK: Used to cancel the wager it owns
-    #[account(signer, mut)]
-    bettor: AccountInfo<'info>
-}
+        transfer_from_bet_vault(
+            bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            &treasury.to_account_info(),
+            &ctx.accounts.system_program,
+            unclaimed_amount,
+        )?;
 
-// Context to claim winnings from a closed bet with a decided outcome
-#[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
+        treasury.total_swept = treasury.total_swept.checked_add(unclaimed_amount).ok_or(ErrorCode::MathOverflow)?;
 
-    #[account(
-        constraint = bet_state.status == BetStateStatus::Closed @ ErrorCode::BetStillOpen,
-        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
-        mut
-    )]
-    bet_state: Account<'info, BetState>,
+        emit!(BetSettled {
+            bet_state: bet_state.key(),
+            total_paid_out: bet_state.total_paid_out,
+            total_fees: bet_state.total_fees,
+            rounding_dust: bet_state.rounding_dust,
+            unclaimed_swept: bet_state.unclaimed_swept,
+            timestamp: bet_state.settled_at,
+        });
 
-    #[account(
-        has_one = bet_state, 
-        has_one = bettor,
-        constraint = wager_detail.bettor == bettor.key() @ ErrorCode::NotAccountOwnerToClaimWinnings,
-        mut,
-        close = bettor
-    )]
-    wager_detail: Account<'info, WagerDetail>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToClaimWinnings,
-        constraint = user_account.active_wagers.len() > 0 @ ErrorCode::ActiveWagersEmpty,
-    )]
-    user_account: Account<'info, UserAccount>,
+    // Endpoint that closes a settled bet state once its retention period is over, every wager on it is
+    // resolved and a token market's vault is closed, returning its rent and its vault's rent to the creator
+    pub fn close_settled_bet_state(ctx: Context<CloseSettledBetState>) -> Result<()> {
 
-    /// CHECK: Used to pay for the bet state account
-    #[account(signer, mut)]
-    bettor: AccountInfo<'info>,
-}
+        let bet_state = &ctx.accounts.bet_state;
+        let retained_until = bet_state.settled_at
+            .checked_add(SETTLED_BET_RETENTION_SECONDS)
+            .ok_or(ErrorCode::MathOverflow)?;
 
-// Context for bet creator to cancel a bet when it's empty
-#[derive(Accounts)]
-pub struct CancelBetState<'info>{
-    #[account(
-        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
-        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
-        constraint = bet_state.creator == bettor.key() @ ErrorCode::NotBetCreator,
-        constraint = bet_state.running_total_pool == 0 @ ErrorCode::FundsStillInPlay,
-        mut,
-        close = bettor
-    )]
-    bet_state: Account<'info, BetState>,
+        if Clock::get()?.unix_timestamp < retained_until {
+            return Err(error!(ErrorCode::RetentionPeriodNotOver));
+        }
 
-    /// CHECK: Used to cancel a wager
-    #[account(signer, mut)]
-    bettor: AccountInfo<'info>
+        close_bet_vault(
+            bet_state,
+            &ctx.accounts.bet_vault,
+            *ctx.bumps.get("bet_vault").unwrap(),
+            &ctx.accounts.bet_creator,
+            &ctx.accounts.system_program,
+        )?;
 
-}
+        emit!(BetStateClosed {
+            bet_state: bet_state.key(),
+            creator: bet_state.creator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-// Context for closing a bet before the actual event takes place
-#[derive(Accounts)]
-pub struct CloseBetState<'info> {
-    #[account(
-        constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
-        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
-        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
-        mut
-    )]
-    bet_state: Account<'info, BetState>,
+        Ok(())
+    }
 
-    /// CHECK: Not written to
-    #[account()]
-    bet_creator: AccountInfo<'info>
-    
-}
 
-// General context for a closed bet state
-#[derive(Accounts)]
-pub struct DecideBetState<'info> {
-    #[account(
-        constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
-        constraint = bet_state.status == BetStateStatus::Closed @ ErrorCode::BetStillOpen,
-        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+    // *** Config Functionality *** //
+
+    // Endpoint that creates the program-wide config, only the program's upgrade authority can create it and
+    // it becomes the config's admin
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        arbitrator: Pubkey,
+        fee_bps: u16,
+        min_wager: u64,
+        max_wager: u64,
+        min_claim_window: u64,
+        min_dispute_window: u64,
+    ) -> Result<()> {
+
+        let config = &mut ctx.accounts.config;
+
+        config.admin = ctx.accounts.admin.key();
+        config.arbitrator = arbitrator;
+        config.treasury = ctx.accounts.treasury.key();
+        config.pause = PauseFlags::default();
+        config.bump = *ctx.bumps.get("config").unwrap();
+        config.set_wager_parameters(fee_bps, min_wager, max_wager)?;
+        config.set_window_minimums(min_claim_window, min_dispute_window)?;
+
+        emit!(ConfigInitialized {
+            config: config.key(),
+            admin: config.admin,
+            arbitrator: config.arbitrator,
+            treasury: config.treasury,
+            fee_bps: config.fee_bps,
+            min_wager: config.min_wager,
+            max_wager: config.max_wager,
+            min_claim_window: config.min_claim_window,
+            min_dispute_window: config.min_dispute_window,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that allows the config admin to change the fee, wager limits and minimum claim and dispute windows
+    // or hand over the admin and arbitrator roles. Markets keep the arbitrator they were created with.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Pubkey,
+        arbitrator: Pubkey,
+        fee_bps: u16,
+        min_wager: u64,
+        max_wager: u64,
+        min_claim_window: u64,
+        min_dispute_window: u64,
+    ) -> Result<()> {
+
+        let config = &mut ctx.accounts.config;
+
+        config.admin = admin;
+        config.arbitrator = arbitrator;
+        config.set_wager_parameters(fee_bps, min_wager, max_wager)?;
+        config.set_window_minimums(min_claim_window, min_dispute_window)?;
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            arbitrator: config.arbitrator,
+            fee_bps: config.fee_bps,
+            min_wager: config.min_wager,
+            max_wager: config.max_wager,
+            min_claim_window: config.min_claim_window,
+            min_dispute_window: config.min_dispute_window,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that allows the config admin to pause or unpause new markets, wagers, claims and withdrawals independently
+    pub fn set_pause_flags(ctx: Context<UpdateConfig>, pause: PauseFlags) -> Result<()> {
+
+        let now = Clock::get()?.unix_timestamp;
+
+        ctx.accounts.config.set_pause(pause, now)?;
+
+        emit!(PauseFlagsUpdated {
+            pause: ctx.accounts.config.pause,
+            claims_paused_for: ctx.accounts.config.claims_paused_for,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+
+    // *** Treasury Functionality *** //
+
+    // Endpoint that creates the treasury that collects the take fee on every wager, only the program's upgrade
+    // authority can create it and it becomes the treasury's authority
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+
+        let treasury = &mut ctx.accounts.treasury;
+        let authority = &ctx.accounts.authority;
+
+        treasury.authority = authority.key();
+        treasury.total_fees_collected = 0;
+        treasury.total_withdrawn = 0;
+        treasury.total_swept = 0;
+        treasury.bump = *ctx.bumps.get("treasury").unwrap();
+
+        emit!(TreasuryInitialized {
+            treasury: treasury.key(),
+            authority: treasury.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that allows the treasury authority to withdraw collected fees
+    pub fn withdraw_from_treasury(ctx: Context<WithdrawFromTreasury>, lamports: u64) -> Result<()> {
+
+        let treasury = &mut ctx.accounts.treasury;
+        let authority = &mut ctx.accounts.authority;
+
+        if lamports == 0 || lamports > treasury.balance()? {
+            return Err(error!(ErrorCode::InvalidWithdrawalAmount));
+        }
+
+        treasury.total_withdrawn = treasury.total_withdrawn.checked_add(lamports).ok_or(ErrorCode::MathOverflow)?;
+
+        **treasury.to_account_info().try_borrow_mut_lamports()? = treasury.to_account_info().lamports().checked_sub(lamports).ok_or(ProgramError::InvalidArgument)?;
+        **authority.to_account_info().try_borrow_mut_lamports()? = authority.to_account_info().lamports().checked_add(lamports).ok_or(ProgramError::InvalidArgument)?;
+
+        emit!(TreasuryWithdrawal {
+            treasury: treasury.key(),
+            destination: authority.key(),
+            mint: Pubkey::default(),
+            amount: lamports,
+            balance: treasury.balance()?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+
+    // *** User Account Functionality *** //
+    pub fn initialize_user_account(ctx: Context<InitializeUserAccount>) -> Result<()> {
+
+        let user_account = &mut ctx.accounts.user_account;
+        let account_owner = &mut ctx.accounts.account_owner;
+
+        user_account.version = UserAccount::CURRENT_VERSION;
+        user_account.account_owner = account_owner.key();
+        user_account.bump = *ctx.bumps.get("user_account").unwrap();
+        user_account.wins = 0;
+        user_account.losses = 0;
+        user_account.realized_profit = 0;
+        user_account.current_balance = 0;
+
+        open_lamport_vault(&ctx.accounts.account_vault, account_owner, &ctx.accounts.system_program)?;
+
+        emit!(UserAccountOpened {
+            user_account: user_account.key(),
+            account_owner: account_owner.key(),
+            account_vault: ctx.accounts.account_vault.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that moves a user account created from a keypair before user accounts were PDAs into the owner's PDA
+    pub fn migrate_user_account(ctx: Context<MigrateUserAccount>) -> Result<()> {
+
+        let legacy_account_info = ctx.accounts.legacy_user_account.to_account_info();
+        let user_account = &mut ctx.accounts.user_account;
+        let account_owner = &mut ctx.accounts.account_owner;
+
+        let legacy_user_account = read_v1_account::<UserAccount, UserAccountV1>(&legacy_account_info, UserAccountV1::ACCOUNT_LEN)?;
+
+        if legacy_user_account.account_owner != account_owner.key() {
+            return Err(error!(ErrorCode::InvalidAccountOwner));
+        }
+
+        user_account.version = UserAccount::CURRENT_VERSION;
+        user_account.account_owner = legacy_user_account.account_owner;
+        user_account.bump = *ctx.bumps.get("user_account").unwrap();
+        user_account.wins = legacy_user_account.wins;
+        user_account.losses = legacy_user_account.losses;
+        user_account.realized_profit = 0;
+
+        // v1 balances were only bookkeeping, so just the part backed by the legacy account's lamports above its
+        // rent-exempt minimum is carried over
+        let legacy_lamports = legacy_account_info.lamports();
+        let backed_balance = legacy_lamports.saturating_sub(Rent::get()?.minimum_balance(legacy_account_info.data_len()));
+        user_account.current_balance = legacy_user_account.current_balance.min(backed_balance);
+
+        // Legacy wagers are keypair accounts no current instruction resolves. Each one is tracked again once it is
+        // moved into its position with migrate_wager_detail_layout, which needs this account to exist first.
+        user_account.active_wagers = Vec::new();
+
+        open_lamport_vault(&ctx.accounts.account_vault, account_owner, &ctx.accounts.system_program)?;
+
+        // Carry the betting balance over to the new account's vault and refund the legacy account's rent to the owner
+        let rent_refund = legacy_lamports.checked_sub(user_account.current_balance).ok_or(ErrorCode::InsufficientLamports)?;
+        let account_vault = ctx.accounts.account_vault.to_account_info();
+
+        **legacy_account_info.try_borrow_mut_lamports()? = 0;
+        **account_vault.try_borrow_mut_lamports()? = account_vault.lamports().checked_add(user_account.current_balance).ok_or(ProgramError::InvalidArgument)?;
+        **account_owner.to_account_info().try_borrow_mut_lamports()? = account_owner.to_account_info().lamports().checked_add(rent_refund).ok_or(ProgramError::InvalidArgument)?;
+
+        legacy_account_info.try_borrow_mut_data()?.fill(0);
+
+        emit!(UserAccountMigrated {
+            legacy_user_account: legacy_account_info.key(),
+            user_account: user_account.key(),
+            account_owner: user_account.account_owner,
+            current_balance: user_account.current_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that shrinks a user account back down once its wagers have been claimed or cancelled, refunding the freed rent
+    pub fn compact_user_account(ctx: Context<CompactUserAccount>) -> Result<()> {
+
+        let user_account = &mut ctx.accounts.user_account;
+        let account_owner = &mut ctx.accounts.account_owner;
+
+        let capacity = user_account.active_wagers.len().max(UserAccount::INITIAL_WAGER_CAPACITY);
+        let new_len = UserAccount::space_for(capacity) + 8;
+        let account_info = user_account.to_account_info();
+
+        if new_len >= account_info.data_len() {
+            return Ok(());
+        }
+
+        account_info.realloc(new_len, false)?;
+
+        // The betting balance lives in the account's vault, so anything above the new rent-exempt minimum is freed rent
+        let freed_rent = account_info.lamports().saturating_sub(Rent::get()?.minimum_balance(new_len));
+
+        **account_info.try_borrow_mut_lamports()? -= freed_rent;
+        **account_owner.to_account_info().try_borrow_mut_lamports()? = account_owner.to_account_info().lamports().checked_add(freed_rent).ok_or(ProgramError::InvalidArgument)?;
+
+        emit!(UserAccountCompacted {
+            user_account: user_account.key(),
+            account_owner: account_owner.key(),
+            data_len: new_len as u64,
+            freed_rent,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that closes an empty user account, its rent and its vault's rent go back to the owner
+    pub fn close_user_account(ctx: Context<CloseUserAccount>) -> Result<()> {
+
+        let user_account = &ctx.accounts.user_account;
+        let account_vault = &ctx.accounts.account_vault;
+        let vault_lamports = account_vault.lamports();
+
+        transfer_from_account_vault(
+            user_account,
+            account_vault,
+            *ctx.bumps.get("account_vault").unwrap(),
+            &ctx.accounts.account_owner,
+            &ctx.accounts.system_program,
+            vault_lamports,
+        )?;
+
+        emit!(UserAccountClosed {
+            user_account: user_account.key(),
+            account_owner: user_account.account_owner,
+            // Everything the vault held, its rent included, the account's own rent is returned on top
+            vault_lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_into_account(ctx: Context<DepositIntoAccount>, lamports: u64) -> Result<()> {
+
+        if lamports == 0 {
+            return Err(error!(ErrorCode::InvalidDepositAmount));
+        }
+
+        let user_account = &mut ctx.accounts.user_account;
+        let account_owner = &ctx.accounts.account_owner;
+        let system_program = &ctx.accounts.system_program;
+
+        // Move the deposit from the owner's wallet into the user's vault, the balance only tracks lamports that actually arrived
+        invoke(
+            &system_instruction::transfer(&account_owner.key(), &ctx.accounts.account_vault.key(), lamports),
+            &[
+                account_owner.to_account_info(),
+                ctx.accounts.account_vault.to_account_info(),
+                system_program.to_account_info(),
+            ],
+        )?;
+
+        user_account.current_balance = user_account.current_balance.checked_add(lamports).ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(Deposit {
+            user_account: user_account.key(),
+            account_owner: account_owner.key(),
+            mint: Pubkey::default(),
+            amount: lamports,
+            balance: user_account.current_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that withdraws from the betting balance, capped at what the vault holds above its rent-exempt minimum
+    pub fn withdraw_from_account(ctx: Context<WithdrawFromAccount>, lamports: u64) -> Result<()> {
+
+        let user_account = &mut ctx.accounts.user_account;
+        let account_owner = &mut ctx.accounts.account_owner;
+
+        user_account.current_balance.checked_sub(lamports).ok_or(ErrorCode::InvalidWithdrawalAmount)?;
+
+        if lamports > withdrawable_lamports(&ctx.accounts.account_vault)? {
+            return Err(error!(ErrorCode::InsufficientLamports));
+        }
+
+        user_account.current_balance -= lamports;
+
+        transfer_from_account_vault(
+            user_account,
+            &ctx.accounts.account_vault,
+            *ctx.bumps.get("account_vault").unwrap(),
+            account_owner,
+            &ctx.accounts.system_program,
+            lamports,
+        )?;
+
+        emit!(Withdrawal {
+            user_account: user_account.key(),
+            account_owner: account_owner.key(),
+            mint: Pubkey::default(),
+            amount: lamports,
+            balance: user_account.current_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that resets the betting balance to what the account's vault actually holds above its rent-exempt minimum
+    pub fn sync_balance(ctx: Context<SyncBalance>) -> Result<()> {
+
+        let user_account = &mut ctx.accounts.user_account;
+        let old_balance = user_account.current_balance;
+
+        user_account.current_balance = withdrawable_lamports(&ctx.accounts.account_vault)?;
+
+        emit!(BalanceSynced {
+            user_account: user_account.key(),
+            account_owner: user_account.account_owner,
+            old_balance,
+            new_balance: user_account.current_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+
+    // *** Token Collateral Functionality *** //
+    // Token markets hold their pools in a vault token account owned by the bet state PDA instead of in the
+    // bet's lamport vault. Bettors fund them from a vault per mint owned by their user account PDA, and the
+    // take fee goes to a vault per mint owned by the treasury. The config's wager limits are in lamports and
+    // only apply to lamport markets.
+
+    // Endpoint that creates the user account's vault for one collateral mint
+    pub fn initialize_user_vault(ctx: Context<InitializeUserVault>) -> Result<()> {
+
+        emit!(UserVaultInitialized {
+            user_account: ctx.accounts.user_account.key(),
+            account_owner: ctx.accounts.account_owner.key(),
+            user_vault: ctx.accounts.user_vault.key(),
+            mint: ctx.accounts.collateral_mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that creates the treasury's vault collecting the take fee in one collateral mint
+    pub fn initialize_treasury_vault(ctx: Context<InitializeTreasuryVault>) -> Result<()> {
+
+        emit!(TreasuryVaultInitialized {
+            treasury: ctx.accounts.treasury.key(),
+            authority: ctx.accounts.authority.key(),
+            treasury_vault: ctx.accounts.treasury_vault.key(),
+            mint: ctx.accounts.collateral_mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that moves tokens from the owner's token account into their user account's vault
+    pub fn deposit_tokens_into_account(ctx: Context<DepositTokensIntoAccount>, amount: u64) -> Result<()> {
+
+        if amount == 0 {
+            return Err(error!(ErrorCode::InvalidDepositAmount));
+        }
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source.to_account_info(),
+            to: ctx.accounts.user_vault.to_account_info(),
+            authority: ctx.accounts.account_owner.to_account_info(),
+        };
+
+        token::transfer(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), amount)?;
+
+        emit!(Deposit {
+            user_account: ctx.accounts.user_account.key(),
+            account_owner: ctx.accounts.account_owner.key(),
+            mint: ctx.accounts.user_vault.mint,
+            amount,
+            balance: ctx.accounts.user_vault.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that moves tokens from the user account's vault back to a token account of the owner's choosing
+    pub fn withdraw_tokens_from_account(ctx: Context<WithdrawTokensFromAccount>, amount: u64) -> Result<()> {
+
+        if amount == 0 || amount > ctx.accounts.user_vault.amount {
+            return Err(error!(ErrorCode::InvalidWithdrawalAmount));
+        }
+
+        transfer_from_user_vault(
+            &ctx.accounts.user_account,
+            &ctx.accounts.user_vault,
+            ctx.accounts.destination.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        emit!(Withdrawal {
+            user_account: ctx.accounts.user_account.key(),
+            account_owner: ctx.accounts.account_owner.key(),
+            mint: ctx.accounts.user_vault.mint,
+            amount,
+            balance: ctx.accounts.user_vault.amount.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that places a wager on a token market from the bettor's vault, the amount includes the take fee
+    pub fn place_token_wager(ctx: Context<PlaceTokenWager>, party: u8, amount: u64) -> Result<()> {
+
+        let bet_state = &mut ctx.accounts.bet_state;
+        let wager_detail = &mut ctx.accounts.wager_detail;
+        let user_account = &mut ctx.accounts.user_account;
+        let user_vault = &ctx.accounts.user_vault;
+        let token_program = &ctx.accounts.token_program;
+
+        bet_state.check_betting_window(Clock::get()?.unix_timestamp)?;
+
+        if amount > user_vault.amount {
+            return Err(error!(ErrorCode::InsufficientAccountBalance));
+        }
+
+        let (wager_amount, take_fee) = fixed_point::split_fee_inclusive(amount, ctx.accounts.config.fee_bps)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if wager_amount == 0 {
+            return Err(error!(ErrorCode::WagerTooSmall));
+        }
+
+        record_wager(
+            bet_state,
+            wager_detail,
+            user_account,
+            party,
+            wager_amount,
+            *ctx.bumps.get("wager_detail").unwrap(),
+            &ctx.accounts.bettor_account,
+            &ctx.accounts.system_program,
+        )?;
+
+        bet_state.total_fees = bet_state.total_fees.checked_add(take_fee).ok_or(ErrorCode::MathOverflow)?;
+
+        transfer_from_user_vault(user_account, user_vault, ctx.accounts.market_vault.to_account_info(), token_program, wager_amount)?;
+        transfer_from_user_vault(user_account, user_vault, ctx.accounts.treasury_vault.to_account_info(), token_program, take_fee)?;
+
+        emit!(WagerPlaced {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: wager_detail.bettor,
+            party: wager_detail.party,
+            amount,
+            fee: take_fee,
+            bet_value: wager_detail.bet_value,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that cancels a wager on an open token market, returning its value to the bettor's vault
+    pub fn cancel_token_wager(ctx: Context<CancelTokenWager>) -> Result<()> {
+
+        let bet_state = &mut ctx.accounts.bet_state;
+
+        bet_state.check_betting_window(Clock::get()?.unix_timestamp)?;
+
+        let bet_value = remove_cancelled_wager(bet_state, &ctx.accounts.wager_detail, &mut ctx.accounts.user_account)?;
+
+        transfer_from_market_vault(
+            bet_state,
+            &ctx.accounts.market_vault,
+            ctx.accounts.user_vault.to_account_info(),
+            &ctx.accounts.token_program,
+            bet_value,
+        )?;
+
+        let wager_detail = &ctx.accounts.wager_detail;
+
+        emit!(WagerCancelled {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: wager_detail.bettor,
+            party: wager_detail.party,
+            amount: bet_value,
+            party_one_pool: bet_state.party_one_pool,
+            party_two_pool: bet_state.party_two_pool,
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that pays a winning wager on a token market into the bettor's vault
+    pub fn claim_token_winnings(ctx: Context<ClaimTokenWinnings>) -> Result<()> {
+
+        let bet_state = &mut ctx.accounts.bet_state;
+
+        bet_state.check_claim_window(&ctx.accounts.config, Clock::get()?.unix_timestamp)?;
+
+        let winnings_amount = record_winning_claim(bet_state, &ctx.accounts.wager_detail, &mut ctx.accounts.user_account)?;
+
+        transfer_from_market_vault(
+            bet_state,
+            &ctx.accounts.market_vault,
+            ctx.accounts.user_vault.to_account_info(),
+            &ctx.accounts.token_program,
+            winnings_amount,
+        )?;
+
+        let wager_detail = &ctx.accounts.wager_detail;
+
+        emit!(WinningsClaimed {
+            bet_state: bet_state.key(),
+            wager_detail: wager_detail.key(),
+            bettor: wager_detail.bettor,
+            bet_value: wager_detail.bet_value,
+            amount: winnings_amount,
+            running_total_pool: bet_state.running_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that refunds a wager's full bet value from a voided token market into the bettor's vault
+    pub fn refund_token_wager(ctx: Context<RefundTokenWager>) -> Result<()> {
+
+        let bet_state = &mut ctx.accounts.bet_state;
+
+        let refund_amount = record_refund(bet_state, &ctx.accounts.wager_detail, &mut ctx.accounts.user_account)?;
+
+        transfer_from_market_vault(
+            bet_state,
+            &ctx.accounts.market_vault,
+            ctx.accounts.user_vault.to_account_info(),
+            &ctx.accounts.token_program,
+            refund_amount,
+        )?;
+
+        emit_wager_refunded(bet_state, &ctx.accounts.wager_detail)
+    }
+
+    // Permissionless crank that refunds wagers from a voided or cancelled token market, mirrors refund_wagers.
+    // remaining_accounts holds (wager_detail, user_account, user_vault, bettor) quadruples; once every wager is
+    // refunded the market vault, the bet's vault and the bet state are closed and their rent goes back to the
+    // creator.
+    pub fn refund_token_wagers<'info>(ctx: Context<'_, '_, '_, 'info, RefundTokenWagers<'info>>) -> Result<()> {
+
+        let wagers = ctx.remaining_accounts.chunks_exact(4);
+
+        if !wagers.remainder().is_empty() {
+            return Err(error!(ErrorCode::InvalidRemainingAccounts));
+        }
+
+        for accounts in wagers {
+            let wager_detail: Account<WagerDetail> = Account::try_from(&accounts[0])?;
+            let mut user_account: Account<UserAccount> = Account::try_from(&accounts[1])?;
+            let user_vault = &accounts[2];
+            let bettor = &accounts[3];
+
+            if wager_detail.bet_state != ctx.accounts.bet_state.key() {
+                return Err(error!(ErrorCode::WagerBetStateMismatch));
+            }
+
+            if wager_detail.bettor != bettor.key() {
+                return Err(error!(ErrorCode::WagerBettorMismatch));
+            }
+
+            if user_account.account_owner != bettor.key() {
+                return Err(error!(ErrorCode::NotAccountOwnerToRefundWager));
+            }
+
+            let (user_vault_key, _) = Pubkey::find_program_address(
+                &[b"user_vault", user_account.key().as_ref(), ctx.accounts.bet_state.collateral_mint.as_ref()],
+                ctx.program_id,
+            );
+
+            if user_vault.key() != user_vault_key {
+                return Err(error!(ErrorCode::InvalidVault));
+            }
+
+            let refund_amount = record_refund(&mut ctx.accounts.bet_state, &wager_detail, &mut user_account)?;
+
+            transfer_from_market_vault(
+                &ctx.accounts.bet_state,
+                &ctx.accounts.market_vault,
+                user_vault.clone(),
+                &ctx.accounts.token_program,
+                refund_amount,
+            )?;
+
+            emit_wager_refunded(&ctx.accounts.bet_state, &wager_detail)?;
+
+            // Persist the user account before the next quadruple, which may belong to the same bettor
+            user_account.exit(ctx.program_id)?;
+            wager_detail.close(bettor.clone())?;
+        }
+
+        if ctx.accounts.bet_state.running_total_pool == 0 {
+            ctx.accounts.market_vault.reload()?;
+
+            close_market_vault_account(
+                &ctx.accounts.bet_state,
+                &ctx.accounts.market_vault,
+                ctx.accounts.treasury_vault.to_account_info(),
+                &ctx.accounts.bet_creator,
+                &ctx.accounts.token_program,
+            )?;
+            close_bet_vault(
+                &ctx.accounts.bet_state,
+                &ctx.accounts.bet_vault,
+                *ctx.bumps.get("bet_vault").unwrap(),
+                &ctx.accounts.bet_creator,
+                &ctx.accounts.system_program,
+            )?;
+            ctx.accounts.bet_state.close(ctx.accounts.bet_creator.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    // Endpoint that moves whatever nobody claimed from a token market into the treasury's vault after the
+    // claim deadline and settles the bet
+    pub fn sweep_unclaimed_tokens(ctx: Context<SweepUnclaimedTokens>) -> Result<()> {
+
+        let bet_state = &mut ctx.accounts.bet_state;
+
+        let unclaimed_amount = bet_state.sweep_unclaimed(&ctx.accounts.config, Clock::get()?.unix_timestamp)?;
+
+        transfer_from_market_vault(
+            bet_state,
+            &ctx.accounts.market_vault,
+            ctx.accounts.treasury_vault.to_account_info(),
+            &ctx.accounts.token_program,
+            unclaimed_amount,
+        )?;
+
+        emit!(BetSettled {
+            bet_state: bet_state.key(),
+            total_paid_out: bet_state.total_paid_out,
+            total_fees: bet_state.total_fees,
+            rounding_dust: bet_state.rounding_dust,
+            unclaimed_swept: bet_state.unclaimed_swept,
+            timestamp: bet_state.settled_at,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that closes a token market's vault once every wager is paid out, refunded or swept. Tokens sent
+    // to the vault outside of a wager go to the treasury's vault and the rent goes back to the creator.
+    pub fn close_market_vault(ctx: Context<CloseMarketVault>) -> Result<()> {
+
+        close_market_vault_account(
+            &ctx.accounts.bet_state,
+            &ctx.accounts.market_vault,
+            ctx.accounts.treasury_vault.to_account_info(),
+            &ctx.accounts.bet_creator.to_account_info(),
+            &ctx.accounts.token_program,
+        )
+    }
+
+    // Endpoint that allows the treasury authority to withdraw take fees collected in one collateral mint
+    pub fn withdraw_tokens_from_treasury(ctx: Context<WithdrawTokensFromTreasury>, amount: u64) -> Result<()> {
+
+        let treasury = &ctx.accounts.treasury;
+        let treasury_vault = &ctx.accounts.treasury_vault;
+
+        if amount == 0 || amount > treasury_vault.amount {
+            return Err(error!(ErrorCode::InvalidWithdrawalAmount));
+        }
+
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &[treasury.bump]];
+
+        let cpi_accounts = Transfer {
+            from: treasury_vault.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: treasury.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, &[treasury_seeds]),
+            amount,
+        )?;
+
+        emit!(TreasuryWithdrawal {
+            treasury: treasury.key(),
+            destination: ctx.accounts.destination.key(),
+            mint: treasury_vault.mint,
+            amount,
+            balance: treasury_vault.amount.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+
+    // *** Account Layout Migrations *** //
+    // v1 bet states and wager details were created from keypairs before accounts carried a version byte and a bump.
+    // Migrating them moves them into their PDA in the current layout and closes the legacy account, v1 user
+    // accounts are moved by migrate_user_account.
+
+    // Endpoint that moves a v1 bet state into its PDA. The pools move into the new bet's vault and the legacy
+    // account's rent goes back to the creator, who keeps every role on the bet until handing them over with
+    // set_bet_state_authorities.
+    pub fn migrate_bet_state_layout(
+        ctx: Context<MigrateBetStateLayout>,
+        start: u64,
+        symbol: String,
+        bet_range: u8,
+        claim_window: u64,
+        dispute_window: u64,
+    ) -> Result<()> {
+
+        let legacy_account_info = ctx.accounts.legacy_bet_state.to_account_info();
+        let bet_state = &mut ctx.accounts.bet_state;
+        let bet_creator = &ctx.accounts.bet_creator;
+
+        let legacy_bet_state = read_v1_account::<BetState, BetStateV1>(&legacy_account_info, BetStateV1::ACCOUNT_LEN)?;
+
+        if legacy_bet_state.creator != bet_creator.key() {
+            return Err(error!(ErrorCode::NotBetCreator));
+        }
+
+        if legacy_bet_state.symbol != symbol || legacy_bet_state.start_time != start || legacy_bet_state.bet_range as u8 != bet_range {
+            return Err(error!(ErrorCode::MigrationSeedsMismatch));
+        }
+
+        ctx.accounts.config.check_claim_window(claim_window)?;
+        ctx.accounts.config.check_dispute_window(dispute_window)?;
+        ctx.accounts.config.check_arbitrator_independence(&[legacy_bet_state.creator])?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let migrated_bet_state = legacy_bet_state.into_current(
+            legacy_account_info.key(),
+            *ctx.bumps.get("bet_state").unwrap(),
+            ctx.accounts.config.arbitrator,
+            claim_window,
+            dispute_window,
+            ctx.accounts.config.total_claims_pause(now)?,
+            now,
+        )?;
+        bet_state.set_inner(migrated_bet_state);
+
+        open_lamport_vault(&ctx.accounts.bet_vault, bet_creator, &ctx.accounts.system_program)?;
+
+        // The v1 bet state held its pools in its own lamports, they move into the vault and the rest is rent
+        let legacy_lamports = legacy_account_info.lamports();
+        let rent_refund = legacy_lamports.checked_sub(bet_state.running_total_pool).ok_or(ErrorCode::InsufficientLamports)?;
+        let bet_vault = ctx.accounts.bet_vault.to_account_info();
+
+        **legacy_account_info.try_borrow_mut_lamports()? = 0;
+        **bet_vault.try_borrow_mut_lamports()? = bet_vault.lamports().checked_add(bet_state.running_total_pool).ok_or(ProgramError::InvalidArgument)?;
+        **bet_creator.to_account_info().try_borrow_mut_lamports()? = bet_creator.to_account_info().lamports().checked_add(rent_refund).ok_or(ProgramError::InvalidArgument)?;
+
+        legacy_account_info.try_borrow_mut_data()?.fill(0);
+
+        emit!(BetStateMigrated {
+            legacy_bet_state: legacy_account_info.key(),
+            bet_state: bet_state.key(),
+            running_total_pool: bet_state.running_total_pool,
+            claim_deadline: bet_state.claim_deadline,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that moves a v1 wager detail on a migrated bet into the bettor's position on the same side. The
+    // wager's value is already part of the migrated bet's pools, the legacy account's rent goes back to the bettor.
+    pub fn migrate_wager_detail_layout(ctx: Context<MigrateWagerDetailLayout>, party: u8) -> Result<()> {
+
+        let legacy_account_info = ctx.accounts.legacy_wager_detail.to_account_info();
+        let bet_state = &mut ctx.accounts.bet_state;
+        let wager_detail = &mut ctx.accounts.wager_detail;
+        let user_account = &mut ctx.accounts.user_account;
+        let bettor = &ctx.accounts.bettor;
+
+        let legacy_wager_detail = read_v1_account::<WagerDetail, WagerDetailV1>(&legacy_account_info, WagerDetailV1::ACCOUNT_LEN)?;
+
+        if bet_state.migrated_from == Pubkey::default() || legacy_wager_detail.bet_state != bet_state.migrated_from {
+            return Err(error!(ErrorCode::WagerBetStateMismatch));
+        }
+
+        if legacy_wager_detail.bettor != bettor.key() {
+            return Err(error!(ErrorCode::WagerBettorMismatch));
+        }
+
+        if legacy_wager_detail.party.number() != party {
+            return Err(error!(ErrorCode::InvalidParty));
+        }
+
+        if wager_detail.bettor == Pubkey::default() {
+            open_position(
+                bet_state,
+                wager_detail,
+                user_account,
+                legacy_wager_detail.party,
+                *ctx.bumps.get("wager_detail").unwrap(),
+                bettor,
+                &ctx.accounts.system_program,
+            )?;
+        }
+
+        wager_detail.bet_value = wager_detail.bet_value.checked_add(legacy_wager_detail.bet_value).ok_or(ErrorCode::MathOverflow)?;
+
+        // User accounts migrated before their wagers may still list the legacy wager
+        user_account.active_wagers.retain(|wager| *wager != legacy_account_info.key());
+
+        let rent_refund = legacy_account_info.lamports();
+
+        **legacy_account_info.try_borrow_mut_lamports()? = 0;
+        **bettor.to_account_info().try_borrow_mut_lamports()? = bettor.to_account_info().lamports().checked_add(rent_refund).ok_or(ProgramError::InvalidArgument)?;
+
+        legacy_account_info.try_borrow_mut_data()?.fill(0);
+
+        emit!(WagerDetailMigrated {
+            legacy_wager_detail: legacy_account_info.key(),
+            wager_detail: wager_detail.key(),
+            bettor: bettor.key(),
+            party: wager_detail.party,
+            // The legacy wager's value, now part of the position's bet value
+            bet_value: legacy_wager_detail.bet_value,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Endpoint that rewrites a bet state, wager detail or user account written with an older versioned layout in the
+    // current one, in place. Anyone can upgrade an account, the payer covers the rent if the account has to grow.
+    pub fn upgrade_account_layout(ctx: Context<UpgradeAccountLayout>) -> Result<()> {
+
+        let account_info = ctx.accounts.account.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = &ctx.accounts.system_program;
+
+        let discriminator = account_info.try_borrow_data()?.get(..8).map(<[u8]>::to_vec).ok_or(ErrorCode::InvalidLayoutAccount)?;
+
+        let (from_version, to_version) = if discriminator == BetState::discriminator() {
+            (upgrade_layout::<BetState>(&account_info, &payer, system_program)?, BetState::CURRENT_VERSION)
+        } else if discriminator == WagerDetail::discriminator() {
+            (upgrade_layout::<WagerDetail>(&account_info, &payer, system_program)?, WagerDetail::CURRENT_VERSION)
+        } else if discriminator == UserAccount::discriminator() {
+            (upgrade_layout::<UserAccount>(&account_info, &payer, system_program)?, UserAccount::CURRENT_VERSION)
+        } else {
+            return Err(error!(ErrorCode::InvalidLayoutAccount));
+        };
+
+        emit!(AccountLayoutUpgraded {
+            account: account_info.key(),
+            from_version,
+            to_version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+}
+
+
+// Fills in a new bet state for either kind of market, collateral_mint is Pubkey::default() for lamport markets
+#[allow(clippy::too_many_arguments)]
+fn initialize_market<'info>(
+    bet_state: &mut Account<'info, BetState>,
+    bet_state_bump: u8,
+    bet_creator: &AccountInfo<'info>,
+    price_feed: &AccountInfo<'info>,
+    config: &Config,
+    collateral_mint: Pubkey,
+    start: u64,
+    duration: u64,
+    symbol: String,
+    snapshot_price: u128,
+    bet_range: u8,
+    resolver: Pubkey,
+    admin: Pubkey,
+    claim_window: u64,
+    dispute_window: u64,
+) -> Result<()> {
+    if symbol.len() > BetState::MAX_SYMBOL_LEN {
+        return Err(error!(ErrorCode::SymbolTooLong));
+    }
+
+    config.check_claim_window(claim_window)?;
+    config.check_dispute_window(dispute_window)?;
+    config.check_arbitrator_independence(&[bet_creator.key(), resolver])?;
+
+    bet_state.version = BetState::CURRENT_VERSION;
+    bet_state.bump = bet_state_bump;
+    bet_state.symbol = symbol;
+    bet_state.creator = bet_creator.key();
+    bet_state.resolver = resolver;
+    bet_state.admin = admin;
+    bet_state.arbitrator = config.arbitrator;
+    bet_state.dispute_window = dispute_window;
+    bet_state.resolution = OutcomeResolution::Unproposed;
+    bet_state.proposed_outcome = BetOutcomeStatus::Undecided;
+    bet_state.proposer = Pubkey::default();
+    bet_state.proposed_at = 0;
+    bet_state.disputer = Pubkey::default();
+    bet_state.price_feed = price_feed.key();
+    bet_state.collateral_mint = collateral_mint;
+    bet_state.migrated_from = Pubkey::default();
+    bet_state.open_positions = 0;
+    bet_state.running_total_pool = 0;
+    bet_state.static_total_pool = 0;
+    bet_state.party_one_pool = 0;
+    bet_state.party_two_pool = 0;
+    bet_state.winning_pool_claimed = 0;
+    bet_state.total_paid_out = 0;
+    bet_state.total_fees = 0;
+    bet_state.rounding_dust = 0;
+    bet_state.settled_at = 0;
+    bet_state.unclaimed_swept = 0;
+    bet_state.bet_outcome = BetOutcomeStatus::Undecided;
+    bet_state.status = BetStateStatus::Open;
+    bet_state.start_time = start;
+    bet_state.end_time = start.checked_add(duration).ok_or(ErrorCode::MathOverflow)?;
+    bet_state.claim_window = claim_window;
+    bet_state.claim_deadline = 0;
+
+    bet_state.snapshot_price = snapshot_price;
+    bet_state.snapshot_expo = oracle::price_feed_expo(price_feed)?;
+
+    match bet_range {
+        0 => { bet_state.bet_range = BetRange::NegativeThreeAndOver },
+        1 => { bet_state.bet_range = BetRange::NegativeTwoToThree },
+        2 => { bet_state.bet_range = BetRange::NegativeOneToTwo },
+        3 => { bet_state.bet_range = BetRange::NegativeOneToZero },
+        4 => { bet_state.bet_range = BetRange::ZeroToPositiveOne },
+        5 => { bet_state.bet_range = BetRange::PositiveOneToTwo },
+        6 => { bet_state.bet_range = BetRange::PositiveTwoToThree },
+        7 => { bet_state.bet_range = BetRange::PositiveThreeAndOver },
+        _ => { return Err(error!(ErrorCode::InvalidBetRange)); }
+    };
+
+    emit!(BetStateInitialized {
+        bet_state: bet_state.key(),
+        creator: bet_state.creator,
+        symbol: bet_state.symbol.clone(),
+        bet_range: bet_state.bet_range,
+        snapshot_price: bet_state.snapshot_price,
+        snapshot_expo: bet_state.snapshot_expo,
+        collateral_mint: bet_state.collateral_mint,
+        start_time: bet_state.start_time,
+        end_time: bet_state.end_time,
+        claim_window: bet_state.claim_window,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Adds a wager's value to the bet's pools and to the bettor's position on that side. A new position is
+// tracked on the user account, repeated wagers on the same side top up the existing one.
+#[allow(clippy::too_many_arguments)]
+fn record_wager<'info>(
+    bet_state: &mut Account<'info, BetState>,
+    wager_detail: &mut Account<'info, WagerDetail>,
+    user_account: &mut Account<'info, UserAccount>,
+    party: u8,
+    wager_amount: u64,
+    wager_detail_bump: u8,
+    bettor: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    // add the wager amount to the static total pool and running_total_pool
+    bet_state.static_total_pool += wager_amount;
+    bet_state.running_total_pool += wager_amount;
+
+    // based on which party the bet is placed, assign correct PartyStatus and correct amount to correct party on BetState type
+    let party_status = match party {
+        1 => {
+            bet_state.party_one_pool += wager_amount;
+            PartyStatus::PartyOne
+        },
+        2 => {
+            bet_state.party_two_pool += wager_amount;
+            PartyStatus::PartyTwo
+        },
+        _ => { return Err(error!(ErrorCode::InvalidParty)); }
+    };
+
+    if wager_detail.bettor == Pubkey::default() {
+        open_position(bet_state, wager_detail, user_account, party_status, wager_detail_bump, bettor, system_program)?;
+    }
+
+    wager_detail.bet_value = wager_detail.bet_value.checked_add(wager_amount).ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+// Fills in a freshly created position and starts tracking it on the bet and the bettor's user account
+fn open_position<'info>(
+    bet_state: &mut Account<'info, BetState>,
+    wager_detail: &mut Account<'info, WagerDetail>,
+    user_account: &mut Account<'info, UserAccount>,
+    party: PartyStatus,
+    wager_detail_bump: u8,
+    bettor: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    grow_user_account_if_full(user_account, bettor, system_program)?;
+
+    bet_state.open_positions = bet_state.open_positions.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+    wager_detail.version = WagerDetail::CURRENT_VERSION;
+    wager_detail.bet_state = bet_state.key();
+    wager_detail.bettor = bettor.key();
+    wager_detail.party = party;
+    wager_detail.bet_value = 0;
+    wager_detail.bump = wager_detail_bump;
+
+    user_account.active_wagers.push(wager_detail.key());
+
+    Ok(())
+}
+
+// Takes a cancelled wager's value back out of the bet's pools and stops tracking it on the user account.
+// Returns the amount owed back to the bettor.
+fn remove_cancelled_wager(
+    bet_state: &mut Account<BetState>,
+    wager_detail: &Account<WagerDetail>,
+    user_account: &mut Account<UserAccount>,
+) -> Result<u64> {
+    let bet_value = wager_detail.bet_value;
+
+    // Subtract the user's bet amount from the total pool
+    bet_state.static_total_pool -= bet_value;
+    bet_state.running_total_pool -= bet_value;
+
+    // Subtract the user's bet amount from the respective party's pool
+    match wager_detail.party {
+        PartyStatus::PartyOne => bet_state.party_one_pool -= bet_value,
+        PartyStatus::PartyTwo => bet_state.party_two_pool -= bet_value
+    }
+
+    bet_state.close_position()?;
+    user_account.remove_active_wager(wager_detail.key())?;
+
+    Ok(bet_value)
+}
+
+// Derives the pari-mutuel payout of a winning wager on-chain and records the claim against the bet and the
+// bettor's stats. The last winning claim sweeps the rounding dust. Returns the amount owed to the bettor.
+fn record_winning_claim(
+    bet_state: &mut Account<BetState>,
+    wager_detail: &Account<WagerDetail>,
+    user_account: &mut Account<UserAccount>,
+) -> Result<u64> {
+    // Only wagers on the winning side can claim
+    if bet_state.winning_party() != Some(wager_detail.party) {
+        return Err(error!(ErrorCode::NonWinningParty));
+    }
+
+    let (winnings_amount, rounding_dust) = bet_state.calculate_payout(wager_detail.bet_value)?;
+
+    bet_state.running_total_pool -= winnings_amount;
+    bet_state.winning_pool_claimed += wager_detail.bet_value;
+    bet_state.total_paid_out += winnings_amount;
+    bet_state.rounding_dust += rounding_dust;
+
+    bet_state.close_position()?;
+    user_account.remove_active_wager(wager_detail.key())?;
+
+    // Record the win and the profit on top of the stake
+    user_account.wins += 1;
+    let profit = winnings_amount.checked_sub(wager_detail.bet_value).ok_or(ErrorCode::PayoutCalculationError)?;
+    user_account.realized_profit = user_account.realized_profit
+        .checked_add(profit as i64)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(winnings_amount)
+}
+
+// Records the refund of a wager's full bet value from a voided bet. The take fee paid on the wager stays in
+// the treasury. Returns the amount owed back to the bettor.
+fn record_refund(
+    bet_state: &mut Account<BetState>,
+    wager_detail: &Account<WagerDetail>,
+    user_account: &mut Account<UserAccount>,
+) -> Result<u64> {
+    let refund_amount = wager_detail.bet_value;
+
+    bet_state.running_total_pool = bet_state.running_total_pool
+        .checked_sub(refund_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    bet_state.total_paid_out += refund_amount;
+
+    bet_state.close_position()?;
+    user_account.remove_active_wager(wager_detail.key())?;
+
+    Ok(refund_amount)
+}
+
+// Moves a wager's bet value from a voided bet's vault back into its bettor's vault
+fn refund_wager_into_user_account<'info>(
+    bet_state: &mut Account<'info, BetState>,
+    bet_vault: &AccountInfo<'info>,
+    bet_vault_bump: u8,
+    wager_detail: &Account<'info, WagerDetail>,
+    user_account: &mut Account<'info, UserAccount>,
+    account_vault: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let refund_amount = record_refund(bet_state, wager_detail, user_account)?;
+
+    user_account.current_balance = user_account.current_balance
+        .checked_add(refund_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    transfer_from_bet_vault(bet_state, bet_vault, bet_vault_bump, account_vault, system_program, refund_amount)
+}
+
+// Refunds always return the wager's full bet value, so the event is the same for lamport and token markets
+fn emit_wager_refunded(bet_state: &Account<BetState>, wager_detail: &Account<WagerDetail>) -> Result<()> {
+    emit!(WagerRefunded {
+        bet_state: bet_state.key(),
+        wager_detail: wager_detail.key(),
+        bettor: wager_detail.bettor,
+        amount: wager_detail.bet_value,
+        running_total_pool: bet_state.running_total_pool,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Lamport vaults are system-owned PDAs without data: [b"bet_vault", bet_state] holds a bet's pools and
+// outcome bonds, [b"account_vault", user_account] holds a user's betting balance. A vault always holds its
+// rent-exempt minimum on top of the funds it accounts for, so only the system program can move lamports out
+// of it and only with the vault's signature.
+
+// Tops a lamport vault up to its rent-exempt minimum so it can start holding funds
+fn open_lamport_vault<'info>(
+    vault: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_top_up = Rent::get()?.minimum_balance(0).saturating_sub(vault.lamports());
+
+    if rent_top_up > 0 {
+        invoke(
+            &system_instruction::transfer(&payer.key(), &vault.key(), rent_top_up),
+            &[payer.clone(), vault.clone(), system_program.clone()],
+        )?;
+    }
+
+    Ok(())
+}
+
+// Lamports a vault can pay out without dropping below its rent-exempt minimum
+fn withdrawable_lamports(vault: &AccountInfo) -> Result<u64> {
+    Ok(vault.lamports().saturating_sub(Rent::get()?.minimum_balance(vault.data_len())))
+}
+
+// Moves lamports out of a bet state's vault, signed by the vault PDA
+fn transfer_from_bet_vault<'info>(
+    bet_state: &Account<'info, BetState>,
+    bet_vault: &AccountInfo<'info>,
+    bet_vault_bump: u8,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    let bet_state_key = bet_state.key();
+    let bet_vault_seeds: &[&[u8]] = &[b"bet_vault", bet_state_key.as_ref(), &[bet_vault_bump]];
+
+    invoke_signed(
+        &system_instruction::transfer(&bet_vault.key(), &to.key(), lamports),
+        &[bet_vault.clone(), to.clone(), system_program.clone()],
+        &[bet_vault_seeds],
+    )?;
+
+    Ok(())
+}
+
+// Moves lamports out of a user account's vault, signed by the vault PDA
+fn transfer_from_account_vault<'info>(
+    user_account: &Account<'info, UserAccount>,
+    account_vault: &AccountInfo<'info>,
+    account_vault_bump: u8,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    let user_account_key = user_account.key();
+    let account_vault_seeds: &[&[u8]] = &[b"account_vault", user_account_key.as_ref(), &[account_vault_bump]];
+
+    invoke_signed(
+        &system_instruction::transfer(&account_vault.key(), &to.key(), lamports),
+        &[account_vault.clone(), to.clone(), system_program.clone()],
+        &[account_vault_seeds],
+    )?;
+
+    Ok(())
+}
+
+// Empties a bet state's vault into the creator's wallet when the bet state is closed, the vault holds
+// nothing but its rent by then
+fn close_bet_vault<'info>(
+    bet_state: &Account<'info, BetState>,
+    bet_vault: &AccountInfo<'info>,
+    bet_vault_bump: u8,
+    bet_creator: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    transfer_from_bet_vault(bet_state, bet_vault, bet_vault_bump, bet_creator, system_program, bet_vault.lamports())
+}
+
+// Moves collateral out of a token market's vault, signed by the bet state PDA that owns the vault
+fn transfer_from_market_vault<'info>(
+    bet_state: &Account<'info, BetState>,
+    market_vault: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let start_time = bet_state.start_time.to_le_bytes();
+    let bet_state_seeds: &[&[u8]] = &[
+        b"bet",
+        bet_state.symbol.as_bytes(),
+        &start_time,
+        &[bet_state.bet_range as u8],
+        &[bet_state.bump],
+    ];
+
+    let cpi_accounts = Transfer {
+        from: market_vault.to_account_info(),
+        to,
+        authority: bet_state.to_account_info(),
+    };
+
+    token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, &[bet_state_seeds]),
+        amount,
+    )
+}
+
+// Moves any tokens left in a token market's vault to the treasury's vault and closes it, returning its rent to
+// the creator. market_vault has to hold the vault's current balance.
+fn close_market_vault_account<'info>(
+    bet_state: &Account<'info, BetState>,
+    market_vault: &Account<'info, TokenAccount>,
+    treasury_vault: AccountInfo<'info>,
+    bet_creator: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let swept_amount = market_vault.amount;
+
+    if swept_amount > 0 {
+        transfer_from_market_vault(bet_state, market_vault, treasury_vault, token_program, swept_amount)?;
+    }
+
+    let start_time = bet_state.start_time.to_le_bytes();
+    let bet_state_seeds: &[&[u8]] = &[
+        b"bet",
+        bet_state.symbol.as_bytes(),
+        &start_time,
+        &[bet_state.bet_range as u8],
+        &[bet_state.bump],
+    ];
+
+    let cpi_accounts = CloseAccount {
+        account: market_vault.to_account_info(),
+        destination: bet_creator.clone(),
+        authority: bet_state.to_account_info(),
+    };
+
+    token::close_account(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, &[bet_state_seeds]))?;
+
+    emit!(MarketVaultClosed {
+        bet_state: bet_state.key(),
+        market_vault: market_vault.key(),
+        mint: market_vault.mint,
+        swept_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Moves tokens out of a user's vault, signed by the user account PDA that owns the vault
+fn transfer_from_user_vault<'info>(
+    user_account: &Account<'info, UserAccount>,
+    user_vault: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let user_account_seeds: &[&[u8]] = &[b"user", user_account.account_owner.as_ref(), &[user_account.bump]];
+
+    let cpi_accounts = Transfer {
+        from: user_vault.to_account_info(),
+        to,
+        authority: user_account.to_account_info(),
+    };
+
+    token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, &[user_account_seeds]),
+        amount,
+    )
+}
+
+// Makes room for one more active wager on the user account, reallocating it with the rent paid by the owner
+fn grow_user_account_if_full<'info>(
+    user_account: &mut Account<'info, UserAccount>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let wager_count = user_account.active_wagers.len() + 1;
+
+    if wager_count > UserAccount::MAX_ACTIVE_WAGERS {
+        return Err(error!(ErrorCode::ActiveWagerLimitReached));
+    }
+
+    let account_info = user_account.to_account_info();
+    let required_len = UserAccount::space_for(wager_count) + 8;
+
+    if required_len <= account_info.data_len() {
+        return Ok(());
+    }
+
+    realloc_with_rent(&account_info, required_len, payer, system_program)
+}
+
+// Grows an account to the given length, with the rent it needs on top of what it holds paid by the payer
+fn realloc_with_rent<'info>(
+    account_info: &AccountInfo<'info>,
+    required_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_top_up = Rent::get()?.minimum_balance(required_len).saturating_sub(account_info.lamports());
+
+    if rent_top_up > 0 {
+        invoke(
+            &system_instruction::transfer(&payer.key(), &account_info.key(), rent_top_up),
+            &[payer.clone(), account_info.clone(), system_program.clone()],
+        )?;
+    }
+
+    account_info.realloc(required_len, false)?;
+
+    Ok(())
+}
+
+// Reads a v1 account of type T. v1 accounts predate the version byte and are recognised by their discriminator
+// and the size they were allocated with.
+fn read_v1_account<T: Discriminator, V1: AnchorDeserialize>(account_info: &AccountInfo, account_len: usize) -> Result<V1> {
+    let data = account_info.try_borrow_data()?;
+
+    if data.len() != account_len || data[..8] != T::discriminator() {
+        return Err(error!(ErrorCode::InvalidLegacyAccount));
+    }
+
+    Ok(V1::deserialize(&mut &data[8..])?)
+}
+
+// Account types that carry a layout version byte right after their discriminator, see upgrade_account_layout.
+// Changing a layout bumps CURRENT_VERSION and teaches from_version to read the layout it replaced.
+trait VersionedLayout: AccountSerialize + Discriminator + Sized {
+    const CURRENT_VERSION: u8;
+    // Size of the accounts from before the version byte, those are moved by their own migration
+    const V1_ACCOUNT_LEN: usize;
+
+    // Reads an account written with an older versioned layout, data starts at the version byte. Version 2 is the
+    // first versioned layout, so no account type has an older one yet.
+    fn from_version(_version: u8, _data: &[u8]) -> Result<Self> {
+        Err(error!(ErrorCode::UnsupportedLayoutVersion))
+    }
+
+    // Bytes the account needs in the current layout, discriminator included
+    fn required_len(&self) -> usize;
+}
+
+// Rewrites an account of type T in its current layout in place, reading it with the layout its version byte names.
+// The account grows if the current layout needs more room. Returns the version it was upgraded from.
+fn upgrade_layout<'info, T: VersionedLayout>(
+    account_info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u8> {
+    let (version, upgraded) = {
+        let data = account_info.try_borrow_data()?;
+
+        if data.len() <= 8 || data.len() == T::V1_ACCOUNT_LEN || data[..8] != T::discriminator() {
+            return Err(error!(ErrorCode::InvalidLayoutAccount));
+        }
+
+        match data[8].cmp(&T::CURRENT_VERSION) {
+            Ordering::Less => (data[8], T::from_version(data[8], &data[8..])?),
+            Ordering::Equal => return Err(error!(ErrorCode::LayoutAlreadyCurrent)),
+            Ordering::Greater => return Err(error!(ErrorCode::UnsupportedLayoutVersion)),
+        }
+    };
+
+    let required_len = upgraded.required_len();
+
+    if required_len > account_info.data_len() {
+        realloc_with_rent(account_info, required_len, payer, system_program)?;
+    }
+
+    upgraded.try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])?;
+
+    Ok(version)
+}
+
+
+// ***** BETTING FUNCTIONALITY CONTEXT AND STRUCTS ***** //
+// Adding an account on a context simply means its public key should be provided when sending the instruction
+
+// Context to initialize the bet state
+#[derive(Accounts)]
+#[instruction(start: u64, duration: u64, symbol: String, snapshot_price: u128, bet_range: u8)]
+pub struct InitializeBetState<'info> {
+
+    #[account(
+        init,
+        payer = bet_creator,
+        space = BetState::MAX_SIZE + 8,
+        seeds = [b"bet", symbol.as_bytes(), &start.to_le_bytes(), &[bet_range]],
+        bump
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    /// CHECK: Used to pay for the bet state account
+    #[account(signer, mut)]
+    bet_creator: AccountInfo<'info>,
+
+    /// CHECK: Pyth price account the market resolves against, its owner and layout are checked in the handler
+    #[account()]
+    price_feed: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.markets @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+
+}
+
+// Context to place a wager on a created bet 
+#[derive(Accounts)]
+#[instruction(party: u8)]
+pub struct PlaceWager<'info> {
+
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.collateral_mint == Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor_account,
+        space = WagerDetail::MAX_SIZE + 8,
+        seeds = [b"wager", bet_state.key().as_ref(), bettor_account.key().as_ref(), &[party]],
+        bump,
+        constraint = wager_detail.bettor == Pubkey::default() || wager_detail.bet_state == bet_state.key() @ ErrorCode::WagerBetStateMismatch,
+        constraint = wager_detail.bettor == Pubkey::default() || wager_detail.bettor == bettor_account.key() @ ErrorCode::WagerBettorMismatch,
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor_account.key() @ ErrorCode::NotAccountOwnerToPlaceWager,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
+    /// CHECK: Used to pay for the wager detail account
+    #[account(signer, mut)]
+    bettor_account: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.wagers @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump, address = config.treasury @ ErrorCode::InvalidTreasury)]
+    treasury: Account<'info, Treasury>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context to cancel a wager on a created bet
+#[derive(Accounts)]
+pub struct CancelWager<'info> {
+
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.collateral_mint == Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut,
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut, 
+        close = bettor,
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToCancelWager,
+        constraint = user_account.active_wagers.len() > 0 @ ErrorCode::ActiveWagersEmpty,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
+    /// CHECK: Used to cancel the wager it owns
+    #[account(signer, mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.wagers @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the vaults
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context to claim winnings from a closed bet with a decided outcome
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+
+    #[account(
+        // A swept bet is settled, its late claimers get ClaimWindowExpired from the handler
+        constraint = (
+            bet_state.status == BetStateStatus::Closed || bet_state.status == BetStateStatus::Settled
+        ) @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Void @ ErrorCode::BetIsVoid,
+        constraint = bet_state.resolution == OutcomeResolution::Finalized @ ErrorCode::OutcomeNotFinalized,
+        constraint = bet_state.collateral_mint == Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut,
+        close = bettor
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToClaimWinnings,
+        constraint = user_account.active_wagers.len() > 0 @ ErrorCode::ActiveWagersEmpty,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
+    /// CHECK: Used to pay for the bet state account
+    #[account(signer, mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the vaults
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context to refund a wager from a voided bet
+#[derive(Accounts)]
+pub struct RefundWager<'info> {
+
+    #[account(
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Void @ ErrorCode::BetNotVoid,
+        constraint = bet_state.collateral_mint == Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut,
+        close = bettor
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToRefundWager,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
+    /// CHECK: Used to refund the wager it owns
+    #[account(signer, mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the vaults
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for the refund crank, the wagers to refund are passed in remaining_accounts
+#[derive(Accounts)]
+pub struct RefundWagers<'info> {
+
+    #[account(
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Void @ ErrorCode::BetNotVoid,
+        constraint = bet_state.collateral_mint == Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    /// CHECK: Receives the bet state's rent once the last wager is refunded
+    #[account(mut, address = bet_state.creator @ ErrorCode::NotBetCreator)]
+    bet_creator: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the bet vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context to settle a losing wager from a closed or settled bet with a decided outcome
+#[derive(Accounts)]
+pub struct AcknowledgeLoss<'info> {
+
+    #[account(
+        constraint = (
+            bet_state.status == BetStateStatus::Closed || bet_state.status == BetStateStatus::Settled
+        ) @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Void @ ErrorCode::BetIsVoid,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut,
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut,
+        close = bettor
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToAcknowledgeLoss,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    /// CHECK: Owner of the wager, receives its rent. Does not need to sign so a crank can settle losses.
+    #[account(mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+}
+
+// Context for bet creator to cancel a bet when it's empty
+#[derive(Accounts)]
+pub struct CancelBetState<'info>{
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    bet_creator: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    config: Account<'info, Config>,
+}
+
+// Context for closing a bet before the actual event takes place
+#[derive(Accounts)]
+pub struct CloseBetState<'info> {
+    #[account(
+        constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
+        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    bet_creator: Signer<'info>
+    
+}
+
+// Context for the resolver to propose the outcome of a closed bet state
+#[derive(Accounts)]
+pub struct ProposeBetStateOutcome<'info> {
+    #[account(
+        constraint = bet_state.resolver == resolver.key() @ ErrorCode::NotBetResolver,
+        constraint = bet_state.status == BetStateStatus::Closed @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.resolution == OutcomeResolution::Unproposed @ ErrorCode::OutcomeAlreadyProposed,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    resolver: Signer<'info>,
+
+    /// CHECK: Used to transfer the bond
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for disputing a proposed outcome
+#[derive(Accounts)]
+pub struct DisputeBetStateOutcome<'info> {
+    #[account(
+        constraint = bet_state.resolution == OutcomeResolution::Proposed @ ErrorCode::OutcomeNotProposed,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    disputer: Signer<'info>,
+
+    /// CHECK: Used to transfer the bond
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for finalizing an undisputed proposal
+#[derive(Accounts)]
+pub struct FinalizeBetStateOutcome<'info> {
+    #[account(
+        constraint = bet_state.resolution == OutcomeResolution::Proposed @ ErrorCode::OutcomeNotProposed,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    /// CHECK: Receives its bond back
+    #[account(mut, address = bet_state.proposer @ ErrorCode::InvalidBondRecipient)]
+    proposer: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the bet vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for the arbitrator to settle a disputed proposal
+#[derive(Accounts)]
+pub struct ArbitrateBetStateOutcome<'info> {
+    #[account(
+        constraint = bet_state.arbitrator == arbitrator.key() @ ErrorCode::NotBetArbitrator,
+        constraint = bet_state.resolution == OutcomeResolution::Disputed @ ErrorCode::OutcomeNotDisputed,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    arbitrator: Signer<'info>,
+
+    /// CHECK: Receives both bonds if the arbitrator upholds the proposal
+    #[account(mut, address = bet_state.proposer @ ErrorCode::InvalidBondRecipient)]
+    proposer: AccountInfo<'info>,
+
+    /// CHECK: Receives both bonds if the arbitrator overturns the proposal
+    #[account(mut, address = bet_state.disputer @ ErrorCode::InvalidBondRecipient)]
+    disputer: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the bet vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for the bet's admin to reassign its resolver and admin roles
+#[derive(Accounts)]
+pub struct SetBetStateAuthorities<'info> {
+    #[account(
+        constraint = bet_state.admin == admin.key() @ ErrorCode::NotBetAdmin,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    admin: Signer<'info>
+}
+
+// Context for resolving an ended bet state against its price feed
+#[derive(Accounts)]
+pub struct ResolveBetState<'info> {
+    #[account(
+        // An open bet is closed by the handler once its end time has passed
+        constraint = (
+            bet_state.status == BetStateStatus::Open || bet_state.status == BetStateStatus::Closed
+        ) @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.resolution == OutcomeResolution::Unproposed @ ErrorCode::OutcomeAlreadyProposed,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    /// CHECK: Parsed as a Pyth price account, must be the feed the market was created with
+    #[account(address = bet_state.price_feed @ ErrorCode::InvalidPriceFeed)]
+    price_feed: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    config: Account<'info, Config>,
+}
+
+// General context for a closed and settled bet state
+#[derive(Accounts)]
+pub struct SettleBetState<'info> {
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Closed @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
+        constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    bet_creator: Signer<'info>
+}
+
+// Context for the creator or the treasury authority to sweep unclaimed funds after the claim deadline
+#[derive(Accounts)]
+pub struct SweepUnclaimedFunds<'info> {
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Closed @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Void @ ErrorCode::BetIsVoid,
+        constraint = (
+            bet_state.creator == authority.key() || treasury.authority == authority.key()
+        ) @ ErrorCode::NotSweepAuthority,
+        constraint = bet_state.collateral_mint == Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    treasury: Account<'info, Treasury>,
+
+    authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the bet vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for closing a settled bet state after its retention period
+#[derive(Accounts)]
+pub struct CloseSettledBetState<'info> {
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Settled @ ErrorCode::BetNotSettled,
+        constraint = bet_state.open_positions == 0 @ ErrorCode::PositionsStillOpen,
+        constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut,
+        close = bet_creator
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    /// CHECK: Only checked to hold no data, a token market has to close its market vault first
+    #[account(
+        seeds = [b"vault", bet_state.key().as_ref()],
+        bump,
+        constraint = market_vault.data_is_empty() @ ErrorCode::MarketVaultStillOpen,
+    )]
+    market_vault: AccountInfo<'info>,
+
+    #[account(mut)]
+    bet_creator: Signer<'info>,
+
+    /// CHECK: Used to transfer out of the bet vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+
+// ** Betting Related Types ** //
+// TODO: Incorporate more info about the pyth data
+// PDA derived from [b"bet", symbol, start_time (le bytes), bet_range], so the symbol has to fit in a single 32 byte seed
+#[account]
+pub struct BetState {
+    pub version: u8, // 1, layout version, see migrate_bet_state_layout
+    pub symbol: String, // 4 + MAX_SYMBOL_LEN
+    pub creator: Pubkey, //32, closes and settles the bet
+    pub resolver: Pubkey, // 32, decides the outcome
+    pub admin: Pubkey, // 32, reassigns the resolver and admin roles
+    pub arbitrator: Pubkey, // 32, settles disputed outcome proposals, the config's arbitrator when the bet was created
+    pub dispute_window: u64, // 8, seconds a proposed outcome can be disputed for
+    pub resolution: OutcomeResolution, // 1
+    pub proposed_outcome: BetOutcomeStatus, // 1
+    pub proposer: Pubkey, // 32
+    pub proposed_at: i64, // 8, unix timestamp in seconds
+    pub disputer: Pubkey, // 32
+    pub running_total_pool: u64, // 8
+    pub static_total_pool: u64, // 8
+    pub party_one_pool: u64, // 8
+    pub party_two_pool: u64, // 8
+    pub start_price: u64, // 8
+    pub bet_outcome: BetOutcomeStatus, // 1
+    pub status: BetStateStatus, // 1
+    pub start_time: u64, // 8, unix timestamp in seconds
+    pub end_time: u64, // 8, unix timestamp in seconds
+    pub claim_window: u64, // 8, seconds winnings can be collected for once the outcome is final, refunds never expire
+    pub claim_deadline: u64, // 8, unix timestamp in seconds, 0 until the outcome is final, unclaimed funds can be swept after it
+    pub claims_paused_offset: u64, // 8, the config's total claims pause when the outcome was final
+
+    pub snapshot_price: u128, //16
+    pub snapshot_expo: i32, // 4, the price feed's exponent, snapshot_price and the resolving price both use it
+    pub bet_range: BetRange, // 1
+    pub winning_pool_claimed: u64, // 8
+    pub total_paid_out: u64, // 8, claimed winnings and refunds
+    pub total_fees: u64, // 8, take fees collected on this bet's wagers
+    pub rounding_dust: u64, // 8, swept to the last winning claim
+    pub settled_at: i64, // 8, unix timestamp in seconds, 0 until settled
+    pub unclaimed_swept: u64, // 8, moved to the treasury after the claim deadline
+    pub price_feed: Pubkey, // 32
+    pub bump: u8, // 1
+    pub collateral_mint: Pubkey, // 32, Pubkey::default() for markets settled in lamports
+    pub migrated_from: Pubkey, // 32, the v1 bet state's address for migrated bets, Pubkey::default() otherwise
+    pub open_positions: u32, // 4, wagers not yet claimed, cancelled, refunded or settled as a loss
+}
+
+impl BetState {
+    const MAX_SIZE: usize = 499;
+    const MAX_SYMBOL_LEN: usize = 32;
+
+    pub fn winning_party(&self) -> Option<PartyStatus> {
+        match self.bet_outcome {
+            BetOutcomeStatus::PartyOneWin => Some(PartyStatus::PartyOne),
+            BetOutcomeStatus::PartyTwoWin => Some(PartyStatus::PartyTwo),
+            BetOutcomeStatus::Undecided | BetOutcomeStatus::Void => None,
+        }
+    }
+
+    // Bonds held in the bet's vault: the proposer's while a proposal is pending, both while it is disputed
+    pub fn outstanding_bonds(&self) -> u64 {
+        match self.resolution {
+            OutcomeResolution::Proposed => OUTCOME_BOND_LAMPORTS,
+            OutcomeResolution::Disputed => OUTCOME_BOND_LAMPORTS * 2,
+            OutcomeResolution::Unproposed | OutcomeResolution::Finalized => 0,
+        }
+    }
+
+    // Last moment a proposed outcome can be disputed
+    pub fn dispute_deadline(&self) -> Result<i64> {
+        self.proposed_at
+            .checked_add(self.dispute_window as i64)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    // Stops counting a wager that was claimed, cancelled, refunded or settled as a loss
+    pub fn close_position(&mut self) -> Result<()> {
+        self.open_positions = self.open_positions.checked_sub(1).ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(())
+    }
+
+    // Decides the bet and opens the claim window, which runs from the moment the outcome is final however
+    // long resolution took
+    pub fn finalize_outcome(&mut self, outcome: BetOutcomeStatus, config: &Config, now: i64) -> Result<()> {
+        self.bet_outcome = outcome;
+        self.resolution = OutcomeResolution::Finalized;
+        self.claim_deadline = (now as u64).checked_add(self.claim_window).ok_or(ErrorCode::MathOverflow)?;
+        self.claims_paused_offset = config.total_claims_pause(now)?;
+
+        Ok(())
+    }
+
+    // The claim deadline pushed back by however long claims were paused since the outcome was final
+    pub fn claim_deadline_after_pauses(&self, config: &Config, now: i64) -> Result<i64> {
+        let paused_since_final = config.total_claims_pause(now)?.saturating_sub(self.claims_paused_offset);
+
+        self.claim_deadline
+            .checked_add(paused_since_final)
+            .map(|deadline| deadline as i64)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    // Marks the bet settled with whatever nobody claimed before the claim deadline, returning the
+    // amount to move to the treasury
+    pub fn sweep_unclaimed(&mut self, config: &Config, now: i64) -> Result<u64> {
+        if now <= self.claim_deadline_after_pauses(config, now)? {
+            return Err(error!(ErrorCode::ClaimWindowStillOpen));
+        }
+
+        let unclaimed_amount = self.running_total_pool;
+
+        self.running_total_pool = 0;
+        self.unclaimed_swept = unclaimed_amount;
+        self.status = BetStateStatus::Settled;
+        self.settled_at = now;
+
+        Ok(unclaimed_amount)
+    }
+
+    // Winnings can be collected up to and including the claim deadline
+    pub fn check_claim_window(&self, config: &Config, now: i64) -> Result<()> {
+        if now > self.claim_deadline_after_pauses(config, now)? {
+            return Err(error!(ErrorCode::ClaimWindowExpired));
+        }
+
+        Ok(())
+    }
+
+    // Wagers can only be placed or cancelled between start_time and end_time
+    pub fn check_betting_window(&self, now: i64) -> Result<()> {
+        if now < self.start_time as i64 {
+            return Err(error!(ErrorCode::BetNotStarted));
+        }
+
+        if now >= self.end_time as i64 {
+            return Err(error!(ErrorCode::BettingWindowEnded));
+        }
+
+        Ok(())
+    }
+
+    // Pari-mutuel payout for a winning stake: the stake back plus its pro-rata share of the losing pool,
+    // rounded down. The last winning stake to claim receives whatever is left in the pool, including dust.
+    // Returns the payout and how much of it is rounding dust above the pro-rata share.
+    pub fn calculate_payout(&self, bet_value: u64) -> Result<(u64, u64)> {
+        let (winning_pool, losing_pool) = match self.bet_outcome {
+            BetOutcomeStatus::PartyOneWin => (self.party_one_pool, self.party_two_pool),
+            BetOutcomeStatus::PartyTwoWin => (self.party_two_pool, self.party_one_pool),
+            BetOutcomeStatus::Undecided => return Err(error!(ErrorCode::BetStillUndecided)),
+            BetOutcomeStatus::Void => return Err(error!(ErrorCode::BetIsVoid)),
+        };
+
+        let remaining_winning_stake = winning_pool
+            .checked_sub(self.winning_pool_claimed)
+            .ok_or(ErrorCode::PayoutCalculationError)?;
+
+        if bet_value == 0 || bet_value > remaining_winning_stake {
+            return Err(error!(ErrorCode::PayoutCalculationError));
+        }
+
+        let share_of_losing_pool = fixed_point::mul_div(bet_value, losing_pool, winning_pool, Rounding::Down)
+            .ok_or(ErrorCode::PayoutCalculationError)?;
+
+        let payout = bet_value
+            .checked_add(share_of_losing_pool)
+            .ok_or(ErrorCode::PayoutCalculationError)?;
+
+        if payout > self.running_total_pool {
+            return Err(error!(ErrorCode::PayoutCalculationError));
+        }
+
+        if bet_value == remaining_winning_stake {
+            return Ok((self.running_total_pool, self.running_total_pool - payout));
+        }
+
+        Ok((payout, 0))
+    }
+}
+
+impl VersionedLayout for BetState {
+    const CURRENT_VERSION: u8 = 2;
+    const V1_ACCOUNT_LEN: usize = BetStateV1::ACCOUNT_LEN;
+
+    fn required_len(&self) -> usize {
+        BetState::MAX_SIZE + 8
+    }
+}
+
+// Position PDA derived from [b"wager", bet_state, bettor, party number], one per side of a bet for each bettor
+#[account]
+pub struct WagerDetail {
+    pub version: u8, // 1, layout version, see migrate_wager_detail_layout
+    pub bettor: Pubkey, // 32
+    pub bet_state: Pubkey, // 32
+    pub party: PartyStatus, // 1
+    pub bet_value: u64, // 8
+    pub bump: u8, // 1
+}
+
+impl WagerDetail {
+    const MAX_SIZE: usize = 75;
+}
+
+impl VersionedLayout for WagerDetail {
+    const CURRENT_VERSION: u8 = 2;
+    const V1_ACCOUNT_LEN: usize = WagerDetailV1::ACCOUNT_LEN;
+
+    fn required_len(&self) -> usize {
+        WagerDetail::MAX_SIZE + 8
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
+pub enum BetOutcomeStatus {
+    Undecided,
+    PartyOneWin,
+    PartyTwoWin,
+    Void,
+}
+
+impl BetOutcomeStatus {
+    // Outcome numbers accepted from the resolver and the arbitrator
+    pub fn from_proposal(outcome: u8) -> Result<BetOutcomeStatus> {
+        match outcome {
+            1 => Ok(BetOutcomeStatus::PartyOneWin),
+            2 => Ok(BetOutcomeStatus::PartyTwoWin),
+            3 => Ok(BetOutcomeStatus::Void),
+            _ => Err(error!(ErrorCode::InvalidBetOutcome)),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
+pub enum OutcomeResolution {
+    Unproposed,
+    Proposed,
+    Disputed,
+    Finalized,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
+pub enum PartyStatus {
+    PartyOne,
+    PartyTwo,
+}
+
+impl PartyStatus {
+    // The party number used by place_wager and in wager detail seeds
+    pub fn number(&self) -> u8 {
+        match self {
+            PartyStatus::PartyOne => 1,
+            PartyStatus::PartyTwo => 2,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
+pub enum BetStateStatus {
+    Open,
+    Closed,
+    Settled,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
+pub enum BetRange {
+    NegativeThreeAndOver,
+    NegativeTwoToThree,
+    NegativeOneToTwo,
+    NegativeOneToZero,
+    ZeroToPositiveOne,
+    PositiveOneToTwo,
+    PositiveTwoToThree,
+    PositiveThreeAndOver
+}
+
+
+// ***** TOKEN COLLATERAL FUNCTIONALITY CONTEXT AND STRUCTS ***** //
+
+// Context to initialize a bet state settled in collateral_mint, mirrors InitializeBetState
+#[derive(Accounts)]
+#[instruction(start: u64, duration: u64, symbol: String, snapshot_price: u128, bet_range: u8)]
+pub struct InitializeTokenBetState<'info> {
+
+    #[account(
+        init,
+        payer = bet_creator,
+        space = BetState::MAX_SIZE + 8,
+        seeds = [b"bet", symbol.as_bytes(), &start.to_le_bytes(), &[bet_range]],
+        bump
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(
+        init,
+        payer = bet_creator,
+        seeds = [b"vault", bet_state.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = bet_state,
+    )]
+    market_vault: Account<'info, TokenAccount>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    /// CHECK: Used to pay for the bet state and market vault accounts
+    #[account(signer, mut)]
+    bet_creator: AccountInfo<'info>,
+
+    /// CHECK: Pyth price account the market resolves against, its owner and layout are checked in the handler
+    #[account()]
+    price_feed: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.markets @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserVault<'info> {
+    #[account(
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(
+        init,
+        payer = account_owner,
+        seeds = [b"user_vault", user_account.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = user_account,
+    )]
+    user_vault: Account<'info, TokenAccount>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    account_owner: Signer<'info>,
+
+    token_program: Program<'info, Token>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasuryVault<'info> {
+    #[account(seeds = [b"treasury"], bump = treasury.bump, has_one = authority @ ErrorCode::NotTreasuryAuthority)]
+    treasury: Account<'info, Treasury>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"treasury_vault", collateral_mint.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = treasury,
+    )]
+    treasury_vault: Account<'info, TokenAccount>,
+
+    collateral_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    authority: Signer<'info>,
+
+    token_program: Program<'info, Token>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTokensIntoAccount<'info> {
+    #[account(
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"user_vault", user_account.key().as_ref(), user_vault.mint.as_ref()], bump)]
+    user_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    source: Account<'info, TokenAccount>,
+
+    account_owner: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.deposits @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokensFromAccount<'info> {
+    #[account(
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"user_vault", user_account.key().as_ref(), user_vault.mint.as_ref()], bump)]
+    user_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    destination: Account<'info, TokenAccount>,
+
+    account_owner: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.withdrawals @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+// Context to place a wager on a token market, mirrors PlaceWager
+#[derive(Accounts)]
+#[instruction(party: u8)]
+pub struct PlaceTokenWager<'info> {
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
         mut
     )]
     bet_state: Account<'info, BetState>,
 
-    /// CHECK: Not written to
-    #[account()]
-    bet_creator: AccountInfo<'info>
+    #[account(
+        init_if_needed,
+        payer = bettor_account,
+        space = WagerDetail::MAX_SIZE + 8,
+        seeds = [b"wager", bet_state.key().as_ref(), bettor_account.key().as_ref(), &[party]],
+        bump,
+        constraint = wager_detail.bettor == Pubkey::default() || wager_detail.bet_state == bet_state.key() @ ErrorCode::WagerBetStateMismatch,
+        constraint = wager_detail.bettor == Pubkey::default() || wager_detail.bettor == bettor_account.key() @ ErrorCode::WagerBettorMismatch,
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor_account.key() @ ErrorCode::NotAccountOwnerToPlaceWager,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"user_vault", user_account.key().as_ref(), bet_state.collateral_mint.as_ref()], bump)]
+    user_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Used to pay for the wager detail account
+    #[account(signer, mut)]
+    bettor_account: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.wagers @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury_vault", bet_state.collateral_mint.as_ref()], bump)]
+    treasury_vault: Account<'info, TokenAccount>,
+
+    token_program: Program<'info, Token>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
 }
 
-// General context for a closed and settled bet state
+// Context to cancel a wager on an open token market, mirrors CancelWager
 #[derive(Accounts)]
-pub struct SettleBetState<'info> {
+pub struct CancelTokenWager<'info> {
+    #[account(
+        constraint = bet_state.status == BetStateStatus::Open @ ErrorCode::BetIsClosedOrSettled,
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Undecided @ ErrorCode::BetAlreadyDecided,
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut,
+        close = bettor
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToCancelWager,
+        constraint = !user_account.active_wagers.is_empty() @ ErrorCode::ActiveWagersEmpty,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"user_vault", user_account.key().as_ref(), bet_state.collateral_mint.as_ref()], bump)]
+    user_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Used to cancel the wager it owns
+    #[account(signer, mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.wagers @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+// Context to claim winnings from a decided token market, mirrors ClaimWinnings
+#[derive(Accounts)]
+pub struct ClaimTokenWinnings<'info> {
+    #[account(
+        // A swept bet is settled, its late claimers get ClaimWindowExpired from the handler
+        constraint = (
+            bet_state.status == BetStateStatus::Closed || bet_state.status == BetStateStatus::Settled
+        ) @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Void @ ErrorCode::BetIsVoid,
+        constraint = bet_state.resolution == OutcomeResolution::Finalized @ ErrorCode::OutcomeNotFinalized,
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut,
+        close = bettor
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToClaimWinnings,
+        constraint = !user_account.active_wagers.is_empty() @ ErrorCode::ActiveWagersEmpty,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"user_vault", user_account.key().as_ref(), bet_state.collateral_mint.as_ref()], bump)]
+    user_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Receives the wager detail's rent
+    #[account(signer, mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+// Context to refund a wager from a voided token market, mirrors RefundWager
+#[derive(Accounts)]
+pub struct RefundTokenWager<'info> {
+    #[account(
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Void @ ErrorCode::BetNotVoid,
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(
+        has_one = bet_state @ ErrorCode::WagerBetStateMismatch,
+        has_one = bettor @ ErrorCode::WagerBettorMismatch,
+        seeds = [b"wager", wager_detail.bet_state.as_ref(), wager_detail.bettor.as_ref(), &[wager_detail.party.number()]],
+        bump = wager_detail.bump,
+        mut,
+        close = bettor
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", user_account.account_owner.as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::NotAccountOwnerToRefundWager,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"user_vault", user_account.key().as_ref(), bet_state.collateral_mint.as_ref()], bump)]
+    user_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Used to refund the wager it owns
+    #[account(signer, mut)]
+    bettor: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+// Context for the token refund crank, mirrors RefundWagers with the wagers to refund in remaining_accounts
+#[derive(Accounts)]
+pub struct RefundTokenWagers<'info> {
+    #[account(
+        constraint = bet_state.bet_outcome == BetOutcomeStatus::Void @ ErrorCode::BetNotVoid,
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"treasury_vault", bet_state.collateral_mint.as_ref()], bump)]
+    treasury_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Receives the rent of the bet state and its vaults once the last wager is refunded
+    #[account(mut, address = bet_state.creator @ ErrorCode::NotBetCreator)]
+    bet_creator: AccountInfo<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+
+    /// CHECK: Used to transfer out of the bet vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+// Context for the creator or the treasury authority to sweep a token market after the claim deadline
+#[derive(Accounts)]
+pub struct SweepUnclaimedTokens<'info> {
     #[account(
         constraint = bet_state.status == BetStateStatus::Closed @ ErrorCode::BetStillOpen,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Undecided @ ErrorCode::BetStillUndecided,
+        constraint = bet_state.bet_outcome != BetOutcomeStatus::Void @ ErrorCode::BetIsVoid,
+        constraint = (
+            bet_state.creator == authority.key() || treasury.authority == authority.key()
+        ) @ ErrorCode::NotSweepAuthority,
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"treasury"], bump = treasury.bump)]
+    treasury: Account<'info, Treasury>,
+
+    #[account(mut, seeds = [b"treasury_vault", bet_state.collateral_mint.as_ref()], bump)]
+    treasury_vault: Account<'info, TokenAccount>,
+
+    authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.claims @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+// Context for the creator to close a paid out token market's vault
+#[derive(Accounts)]
+pub struct CloseMarketVault<'info> {
+    #[account(
         constraint = (
-            bet_state.bet_outcome == BetOutcomeStatus::PartyOneWin || bet_state.bet_outcome == BetOutcomeStatus::PartyTwoWin
-        ) @ ErrorCode::BetStillUndecided,
+            bet_state.status == BetStateStatus::Settled || bet_state.status == BetStateStatus::Cancelled
+        ) @ ErrorCode::BetNotSettled,
+        constraint = bet_state.running_total_pool == 0 @ ErrorCode::FundsStillInPlay,
         constraint = bet_state.creator == bet_creator.key() @ ErrorCode::NotBetCreator,
-        mut,
-        close = bet_creator
+        constraint = bet_state.collateral_mint != Pubkey::default() @ ErrorCode::CollateralMismatch,
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
     )]
     bet_state: Account<'info, BetState>,
 
-    /// CHECK: Not written to
-    #[account()]
-    bet_creator: AccountInfo<'info>
+    #[account(mut, seeds = [b"vault", bet_state.key().as_ref()], bump)]
+    market_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"treasury_vault", bet_state.collateral_mint.as_ref()], bump)]
+    treasury_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    bet_creator: Signer<'info>,
+
+    token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawTokensFromTreasury<'info> {
+    #[account(seeds = [b"treasury"], bump = treasury.bump, has_one = authority @ ErrorCode::NotTreasuryAuthority)]
+    treasury: Account<'info, Treasury>,
 
-// ** Betting Related Types ** //
-// TODO: Incorporate more info about the pyth data
-// TODO: Implement a PDA for this account
+    #[account(mut, seeds = [b"treasury_vault", treasury_vault.mint.as_ref()], bump)]
+    treasury_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    destination: Account<'info, TokenAccount>,
+
+    authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.withdrawals @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    token_program: Program<'info, Token>,
+}
+
+
+// ***** CONFIG FUNCTIONALITY CONTEXT AND STRUCTS ***** //
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(init, payer = admin, space = Config::MAX_SIZE + 8, seeds = [b"config"], bump)]
+    config: Account<'info, Config>,
+
+    #[account(seeds = [b"treasury"], bump = treasury.bump)]
+    treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    admin: Signer<'info>,
+
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::NotUpgradeAuthority,
+    )]
+    program_data: Account<'info, ProgramData>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotConfigAdmin,
+    )]
+    config: Account<'info, Config>,
+
+    admin: Signer<'info>
+}
+
+// Program-wide settings read by initialize_bet_state and place_wager, and the pause flags checked by
+// every instruction that creates markets or moves funds
 #[account]
-pub struct BetState {
-    pub symbol: String, // 14
-    pub creator: Pubkey, //32
-    pub running_total_pool: u64, // 8
-    pub static_total_pool: u64, // 8
-    pub party_one_pool: u64, // 8
-    pub party_two_pool: u64, // 8
-    pub start_price: u64, // 8
-    pub bet_outcome: BetOutcomeStatus, // 33
-    pub status: BetStateStatus, // 33
-    pub start_time: u64, // 8
-    pub end_time: u64, // 8
+pub struct Config {
+    pub admin: Pubkey, // 32
+    pub fee_bps: u16, // 2, take rate on every wager
+    pub treasury: Pubkey, // 32, where the take fee is collected
+    pub min_wager: u64, // 8, in lamports including the fee
+    pub max_wager: u64, // 8, in lamports including the fee
+    pub pause: PauseFlags, // 5
+    pub bump: u8, // 1
+    pub min_claim_window: u64, // 8, shortest claim window a market can be created or migrated with
+    pub min_dispute_window: u64, // 8, shortest dispute window a market can be created or migrated with
+    pub claims_paused_at: i64, // 8, unix timestamp in seconds claims were last paused at
+    pub claims_paused_for: u64, // 8, seconds claims spent paused before the current pause, if any
+    pub arbitrator: Pubkey, // 32, rules on disputed proposals of the markets created while it holds the role
+}
 
-    pub snapshot_price: u128, //16
-    pub bet_range: BetRange //33
+impl Config {
+    const MAX_SIZE: usize = 152;
+
+    pub fn set_wager_parameters(&mut self, fee_bps: u16, min_wager: u64, max_wager: u64) -> Result<()> {
+        if fee_bps as u64 > fixed_point::BPS_DENOMINATOR {
+            return Err(error!(ErrorCode::InvalidFeeRate));
+        }
+
+        if min_wager > max_wager {
+            return Err(error!(ErrorCode::InvalidWagerLimits));
+        }
+
+        self.fee_bps = fee_bps;
+        self.min_wager = min_wager;
+        self.max_wager = max_wager;
+
+        Ok(())
+    }
+
+    // Without a claim window a bet could be swept the moment its outcome is final, without a dispute window a
+    // proposal could be finalized before anyone can dispute it
+    pub fn set_window_minimums(&mut self, min_claim_window: u64, min_dispute_window: u64) -> Result<()> {
+        if min_claim_window == 0 {
+            return Err(error!(ErrorCode::InvalidClaimWindow));
+        }
+
+        if min_dispute_window == 0 {
+            return Err(error!(ErrorCode::InvalidDisputeWindow));
+        }
+
+        self.min_claim_window = min_claim_window;
+        self.min_dispute_window = min_dispute_window;
+
+        Ok(())
+    }
+
+    // A creator or resolver who is also the arbitrator could overturn any dispute of their own proposal
+    pub fn check_arbitrator_independence(&self, keys: &[Pubkey]) -> Result<()> {
+        if keys.contains(&self.arbitrator) {
+            return Err(error!(ErrorCode::ArbitratorNotIndependent));
+        }
+
+        Ok(())
+    }
+
+    pub fn check_claim_window(&self, claim_window: u64) -> Result<()> {
+        if claim_window < self.min_claim_window {
+            return Err(error!(ErrorCode::ClaimWindowTooShort));
+        }
+
+        Ok(())
+    }
+
+    // Updates the pause flags and keeps track of how long claims spend paused, claim deadlines are pushed back
+    // by it so a pause never eats into a bettor's time to claim
+    pub fn set_pause(&mut self, pause: PauseFlags, now: i64) -> Result<()> {
+        if pause.claims && !self.pause.claims {
+            self.claims_paused_at = now;
+        }
+
+        if !pause.claims && self.pause.claims {
+            self.claims_paused_for = self.total_claims_pause(now)?;
+        }
+
+        self.pause = pause;
+
+        Ok(())
+    }
+
+    // Seconds claims have spent paused up to now, the current pause included
+    pub fn total_claims_pause(&self, now: i64) -> Result<u64> {
+        if !self.pause.claims {
+            return Ok(self.claims_paused_for);
+        }
+
+        let current_pause = now.saturating_sub(self.claims_paused_at).max(0) as u64;
+
+        self.claims_paused_for
+            .checked_add(current_pause)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn check_dispute_window(&self, dispute_window: u64) -> Result<()> {
+        if dispute_window < self.min_dispute_window {
+            return Err(error!(ErrorCode::DisputeWindowTooShort));
+        }
+
+        Ok(())
+    }
+}
+
+
+// Each scope is paused on its own, so e.g. withdrawals keep working while only new wagers are stopped.
+// Resolving an outcome is never paused: proposals and disputes only move the resolver's and disputer's own
+// bonds, and finalizing, arbitrating, resolving or cancelling a bet starts its claim window, which a pause
+// would only hold up. Config and treasury admin, closing bets and opening or closing vaults only move rent.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PauseFlags {
+    pub markets: bool, // initialize_bet_state, initialize_token_bet_state, migrate_bet_state_layout
+    pub wagers: bool, // place_wager, cancel_wager, migrate_wager_detail_layout and the token variants
+    pub claims: bool, // claim_winnings, acknowledge_loss, refund_wager, refund_wagers, sweep_unclaimed_funds and the token variants
+    pub withdrawals: bool, // withdraw_from_account, withdraw_from_treasury, sync_balance and the token variants
+    pub deposits: bool, // deposit_into_account, deposit_tokens_into_account, migrate_user_account
 }
 
-impl BetState {
-    const MAX_SIZE: usize = 220;
+
+// ***** TREASURY FUNCTIONALITY CONTEXT AND STRUCTS ***** //
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(init, payer = authority, space = Treasury::MAX_SIZE + 8, seeds = [b"treasury"], bump)]
+    treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    authority: Signer<'info>,
+
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::NotUpgradeAuthority,
+    )]
+    program_data: Account<'info, ProgramData>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
 }
 
-#[account]
-pub struct WagerDetail {
-    pub bettor: Pubkey, // 32
-    pub bet_state: Pubkey, // 32
-    pub party: PartyStatus, // 33
-    pub bet_value: u64, // 8
-}
+#[derive(Accounts)]
+pub struct WithdrawFromTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::NotTreasuryAuthority,
+    )]
+    treasury: Account<'info, Treasury>,
 
-impl WagerDetail {
-    const MAX_SIZE: usize = 105;
-}
+    #[account(mut)]
+    authority: Signer<'info>,
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
-pub enum BetOutcomeStatus {
-    Undecided,
-    PartyOneWin,
-    PartyTwoWin,
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.withdrawals @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
-pub enum PartyStatus {
-    PartyOne,
-    PartyTwo,
+// Lamports held by the treasury are always its rent-exempt reserve plus total_fees_collected + total_swept - total_withdrawn
+#[account]
+pub struct Treasury {
+    pub authority: Pubkey, // 32
+    pub total_fees_collected: u64, // 8
+    pub total_withdrawn: u64, // 8
+    pub total_swept: u64, // 8, unclaimed winnings swept from bets after their claim deadline
+    pub bump: u8, // 1
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
-pub enum BetStateStatus {
-    Open,
-    Closed,
-    Settled
-}
+impl Treasury {
+    const MAX_SIZE: usize = 57;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Copy, Debug)]
-pub enum BetRange {
-    NegativeThreeAndOver,
-    NegativeTwoToThree,
-    NegativeOneToTwo,
-    NegativeOneToZero,
-    ZeroToPositiveOne,
-    PositiveOneToTwo,
-    PositiveTwoToThree,
-    PositiveThreeAndOver
+    pub fn balance(&self) -> Result<u64> {
+        self.total_fees_collected
+            .checked_add(self.total_swept)
+            .and_then(|total_in| total_in.checked_sub(self.total_withdrawn))
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
 }
 
 
@@ -547,9 +3765,18 @@ pub enum BetRange {
 
 #[derive(Accounts)]
 pub struct InitializeUserAccount<'info> {
-    #[account(init, payer = account_owner, space = UserAccount::MAX_SIZE + 8)]
+    #[account(
+        init,
+        payer = account_owner,
+        space = UserAccount::INITIAL_SIZE + 8,
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump
+    )]
     user_account: Account<'info, UserAccount>,
 
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
     /// CHECK: Used to create a user account
     #[account(mut)]
     account_owner: Signer<'info>,
@@ -559,58 +3786,718 @@ pub struct InitializeUserAccount<'info> {
     pub system_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateUserAccount<'info> {
+    /// CHECK: Deserialized by hand in the handler, the legacy layout predates the bump field
+    #[account(
+        mut,
+        owner = *program_id,
+        constraint = legacy_user_account.key() != user_account.key() @ ErrorCode::InvalidLegacyAccount,
+    )]
+    legacy_user_account: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = account_owner,
+        space = UserAccount::INITIAL_SIZE + 8,
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    account_owner: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.deposits @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompactUserAccount<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    account_owner: Signer<'info>
+}
+
 #[derive(Accounts)]
 pub struct CloseUserAccount<'info> {
     #[account(
         mut, 
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
         constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
         constraint = user_account.current_balance == 0 @ ErrorCode::AccountBalanceNotEmpty,
+        // An open wager still owes its payout or refund to this account
+        constraint = user_account.active_wagers.is_empty() @ ErrorCode::ActiveWagersRemaining,
         close = account_owner)]
     user_account: Account<'info, UserAccount>,
 
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
     /// CHECK: Used to close account
     #[account(mut)]
-    account_owner: Signer<'info>
+    account_owner: Signer<'info>,
+
+    /// CHECK: Used to transfer out of the account vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
 pub struct DepositIntoAccount<'info> {
     #[account(
         mut,
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
         constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
     )]
     user_account: Account<'info, UserAccount>,
 
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
     /// CHECK: used to deposit funds into user account
     #[account(mut)]
-    account_owner: Signer<'info>
+    account_owner: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.deposits @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer the deposit
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
 pub struct WithdrawFromAccount<'info> {
     #[account(
         mut,
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
         constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
         constraint = user_account.current_balance > 0 @ ErrorCode::CannotWithdrawFromEmptyAccount,
     )]
     user_account: Account<'info, UserAccount>,
 
+    #[account(mut, seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
     /// CHECK: used to withdraw funds from user account
     #[account(mut)]
-    account_owner: Signer<'info>
+    account_owner: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.withdrawals @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Used to transfer out of the account vault
+    #[account(address = system_program::ID)]
+    system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncBalance<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", account_owner.key().as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == account_owner.key() @ ErrorCode::InvalidAccountOwner,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(seeds = [b"account_vault", user_account.key().as_ref()], bump)]
+    account_vault: SystemAccount<'info>,
+
+    account_owner: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.withdrawals @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
 }
 
+// PDA derived from [b"user", account_owner], one per wallet. The account starts with room for five active
+// wagers and is reallocated 32 bytes at a time as more are placed, see grow_user_account_if_full. The betting
+// balance is held in the account's lamport vault, see open_lamport_vault
 #[account]
 pub struct UserAccount {
+    pub version: u8, //1, layout version, see migrate_user_account
     pub account_owner: Pubkey, //32
     pub wins: u64, //8
     pub losses: u64, //8
-    pub active_wagers: Vec<Pubkey>, //4 + (32 * 5),
+    pub realized_profit: i64, //8, lamports won minus lamports lost across settled wagers
+    pub active_wagers: Vec<Pubkey>, //4 + (32 * n),
     pub current_balance: u64, //8
+    pub bump: u8, //1
 }
 
 impl UserAccount {
-    const MAX_SIZE: usize = 220;
+    const BASE_SIZE: usize = 70;
+    const INITIAL_WAGER_CAPACITY: usize = 5;
+    const INITIAL_SIZE: usize = UserAccount::BASE_SIZE + 32 * UserAccount::INITIAL_WAGER_CAPACITY;
+    const MAX_ACTIVE_WAGERS: usize = 64;
+
+    pub fn space_for(active_wagers: usize) -> usize {
+        UserAccount::BASE_SIZE + 32 * active_wagers
+    }
+
+    pub fn remove_active_wager(&mut self, wager_detail: Pubkey) -> Result<()> {
+        if let Some(keypos) = self.active_wagers.iter().position(|x| *x == wager_detail) {
+            self.active_wagers.remove(keypos);
+            Ok(())
+        } else {
+            Err(error!(ErrorCode::ActiveWagerNotFound))
+        }
+    }
+}
+
+impl VersionedLayout for UserAccount {
+    const CURRENT_VERSION: u8 = 2;
+    const V1_ACCOUNT_LEN: usize = UserAccountV1::ACCOUNT_LEN;
+
+    // Upgrading never gives up the room the account has grown to for its wagers
+    fn required_len(&self) -> usize {
+        UserAccount::space_for(self.active_wagers.len().max(UserAccount::INITIAL_WAGER_CAPACITY)) + 8
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(start: u64, symbol: String, bet_range: u8)]
+pub struct MigrateBetStateLayout<'info> {
+    /// CHECK: Deserialized by hand in the handler, v1 layouts predate the version byte
+    #[account(mut, owner = *program_id)]
+    legacy_bet_state: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = bet_creator,
+        space = BetState::MAX_SIZE + 8,
+        seeds = [b"bet", symbol.as_bytes(), &start.to_le_bytes(), &[bet_range]],
+        bump
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(mut, seeds = [b"bet_vault", bet_state.key().as_ref()], bump)]
+    bet_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    bet_creator: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.markets @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(party: u8)]
+pub struct MigrateWagerDetailLayout<'info> {
+    /// CHECK: Deserialized by hand in the handler, v1 layouts predate the version byte
+    #[account(mut, owner = *program_id)]
+    legacy_wager_detail: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"bet", bet_state.symbol.as_bytes(), &bet_state.start_time.to_le_bytes(), &[bet_state.bet_range as u8]],
+        bump = bet_state.bump,
+        mut,
+    )]
+    bet_state: Account<'info, BetState>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = WagerDetail::MAX_SIZE + 8,
+        seeds = [b"wager", bet_state.key().as_ref(), bettor.key().as_ref(), &[party]],
+        bump,
+    )]
+    wager_detail: Account<'info, WagerDetail>,
+
+    #[account(
+        mut,
+        seeds = [b"user", bettor.key().as_ref()],
+        bump = user_account.bump,
+        constraint = user_account.account_owner == bettor.key() @ ErrorCode::InvalidAccountOwner,
+    )]
+    user_account: Account<'info, UserAccount>,
+
+    #[account(mut)]
+    bettor: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, constraint = !config.pause.wagers @ ErrorCode::ProgramPaused)]
+    config: Account<'info, Config>,
+
+    /// CHECK: Not read from or written to
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeAccountLayout<'info> {
+    /// CHECK: Deserialized by hand in the handler, older layouts don't deserialize as the current one
+    #[account(mut, owner = *program_id)]
+    account: AccountInfo<'info>,
+
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// CHECK: Used to top up the account's rent
+    #[account(address = system_program::ID)]
+    pub system_program: AccountInfo<'info>,
+}
+
+// Bet state layout before the version byte, allocated from a keypair with the old hand-counted size. Its pools
+// were held in the account's own lamports.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct BetStateV1 {
+    pub symbol: String,
+    pub creator: Pubkey,
+    pub running_total_pool: u64,
+    pub static_total_pool: u64,
+    pub party_one_pool: u64,
+    pub party_two_pool: u64,
+    pub start_price: u64,
+    pub bet_outcome: BetOutcomeStatus,
+    pub status: BetStateStatus,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub snapshot_price: u128,
+    pub bet_range: BetRange,
+}
+
+impl BetStateV1 {
+    const ACCOUNT_LEN: usize = 220 + 8;
+
+    // v1 bets have no price feed and can only be resolved through a proposal. A decided v1 bet never recorded
+    // when it was decided, so its claim window starts at migration. Payouts v1 made were computed off-chain,
+    // everything taken out of the pool counts as paid out. v1 never recorded which winning stakes were paid, so a
+    // decided bet that paid out any winnings can't be carried over without breaking the last claimer's payout.
+    #[allow(clippy::too_many_arguments)]
+    pub fn into_current(
+        self,
+        migrated_from: Pubkey,
+        bump: u8,
+        arbitrator: Pubkey,
+        claim_window: u64,
+        dispute_window: u64,
+        claims_paused_offset: u64,
+        now: i64,
+    ) -> Result<BetState> {
+        if self.bet_outcome != BetOutcomeStatus::Undecided && self.running_total_pool != self.static_total_pool {
+            return Err(error!(ErrorCode::LegacyPayoutsStarted));
+        }
+
+        let (resolution, claim_deadline) = match self.bet_outcome {
+            BetOutcomeStatus::Undecided => (OutcomeResolution::Unproposed, 0),
+            _ => (OutcomeResolution::Finalized, (now as u64).checked_add(claim_window).ok_or(ErrorCode::MathOverflow)?),
+        };
+
+        Ok(BetState {
+            version: BetState::CURRENT_VERSION,
+            creator: self.creator,
+            resolver: self.creator,
+            admin: self.creator,
+            arbitrator,
+            dispute_window,
+            resolution,
+            proposed_outcome: self.bet_outcome,
+            proposer: Pubkey::default(),
+            proposed_at: 0,
+            disputer: Pubkey::default(),
+            running_total_pool: self.running_total_pool,
+            static_total_pool: self.static_total_pool,
+            party_one_pool: self.party_one_pool,
+            party_two_pool: self.party_two_pool,
+            start_price: self.start_price,
+            bet_outcome: self.bet_outcome,
+            status: self.status,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            claim_window,
+            claim_deadline,
+            claims_paused_offset,
+            symbol: self.symbol,
+            snapshot_price: self.snapshot_price,
+            snapshot_expo: 0,
+            bet_range: self.bet_range,
+            winning_pool_claimed: 0,
+            total_paid_out: self.static_total_pool.checked_sub(self.running_total_pool).ok_or(ErrorCode::MathOverflow)?,
+            total_fees: 0,
+            rounding_dust: 0,
+            settled_at: 0,
+            unclaimed_swept: 0,
+            price_feed: Pubkey::default(),
+            bump,
+            collateral_mint: Pubkey::default(),
+            migrated_from,
+            open_positions: 0,
+        })
+    }
+}
+
+// Wager detail layout before the version byte, allocated from a keypair with the old hand-counted size
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct WagerDetailV1 {
+    pub bettor: Pubkey,
+    pub bet_state: Pubkey,
+    pub party: PartyStatus,
+    pub bet_value: u64,
+}
+
+impl WagerDetailV1 {
+    const ACCOUNT_LEN: usize = 105 + 8;
+}
+
+// User account layout before the version byte, allocated from a keypair with the old hand-counted size. Its
+// betting balance was held in the account's own lamports.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UserAccountV1 {
+    pub account_owner: Pubkey,
+    pub wins: u64,
+    pub losses: u64,
+    pub active_wagers: Vec<Pubkey>,
+    pub current_balance: u64,
+}
+
+impl UserAccountV1 {
+    const ACCOUNT_LEN: usize = 220 + 8;
+}
+
+
+// ***** Events ***** //
+// Emitted by the endpoint that makes each state transition. Amounts are in lamports, or in the collateral mint
+// for token markets, pools are the bet's pools after the transition and timestamps are the cluster's unix time.
+
+#[event]
+pub struct BetStateInitialized {
+    pub bet_state: Pubkey,
+    pub creator: Pubkey,
+    pub symbol: String,
+    pub bet_range: BetRange,
+    pub snapshot_price: u128,
+    pub snapshot_expo: i32,
+    // Pubkey::default() for lamport markets
+    pub collateral_mint: Pubkey,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub claim_window: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WagerPlaced {
+    pub bet_state: Pubkey,
+    pub wager_detail: Pubkey,
+    pub bettor: Pubkey,
+    pub party: PartyStatus,
+    // Amount taken from the bettor, the bet value plus the take fee
+    pub amount: u64,
+    pub fee: u64,
+    pub bet_value: u64,
+    pub party_one_pool: u64,
+    pub party_two_pool: u64,
+    pub running_total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WagerCancelled {
+    pub bet_state: Pubkey,
+    pub wager_detail: Pubkey,
+    pub bettor: Pubkey,
+    pub party: PartyStatus,
+    pub amount: u64,
+    pub party_one_pool: u64,
+    pub party_two_pool: u64,
+    pub running_total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetClosed {
+    pub bet_state: Pubkey,
+    pub party_one_pool: u64,
+    pub party_two_pool: u64,
+    pub static_total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetCancelled {
+    pub bet_state: Pubkey,
+    pub running_total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetAuthoritiesUpdated {
+    pub bet_state: Pubkey,
+    pub resolver: Pubkey,
+    pub admin: Pubkey,
+    pub arbitrator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OutcomeProposed {
+    pub bet_state: Pubkey,
+    pub proposer: Pubkey,
+    pub outcome: BetOutcomeStatus,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OutcomeDisputed {
+    pub bet_state: Pubkey,
+    pub disputer: Pubkey,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OutcomeDecided {
+    pub bet_state: Pubkey,
+    pub outcome: BetOutcomeStatus,
+    pub party_one_pool: u64,
+    pub party_two_pool: u64,
+    pub running_total_pool: u64,
+    pub claim_deadline: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WinningsClaimed {
+    pub bet_state: Pubkey,
+    pub wager_detail: Pubkey,
+    pub bettor: Pubkey,
+    pub bet_value: u64,
+    // Payout including the returned bet value
+    pub amount: u64,
+    pub running_total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LossAcknowledged {
+    pub bet_state: Pubkey,
+    pub wager_detail: Pubkey,
+    pub bettor: Pubkey,
+    pub bet_value: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WagerRefunded {
+    pub bet_state: Pubkey,
+    pub wager_detail: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub running_total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetSettled {
+    pub bet_state: Pubkey,
+    pub total_paid_out: u64,
+    pub total_fees: u64,
+    pub rounding_dust: u64,
+    pub unclaimed_swept: u64,
+    pub timestamp: i64,
+}
+
+// Deposits and withdrawals of lamports carry the default pubkey as their mint
+#[event]
+pub struct Deposit {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    // Balance after the deposit, the betting balance for lamports or the vault's balance for tokens
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Withdrawal {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BalanceSynced {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub old_balance: u64,
+    pub new_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserAccountOpened {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub account_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserAccountCompacted {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    // Size of the account after compacting, its discriminator included
+    pub data_len: u64,
+    pub freed_rent: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserAccountClosed {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub vault_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserVaultInitialized {
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub user_vault: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetStateClosed {
+    pub bet_state: Pubkey,
+    pub creator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketVaultClosed {
+    pub bet_state: Pubkey,
+    pub market_vault: Pubkey,
+    pub mint: Pubkey,
+    // Tokens sent to the vault outside of a wager, moved to the treasury's vault
+    pub swept_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryInitialized {
+    pub treasury: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryVaultInitialized {
+    pub treasury: Pubkey,
+    pub authority: Pubkey,
+    pub treasury_vault: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+// Withdrawals of lamports carry the default pubkey as their mint, like Deposit and Withdrawal
+#[event]
+pub struct TreasuryWithdrawal {
+    pub treasury: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    // What the treasury still holds in that mint after the withdrawal
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigInitialized {
+    pub config: Pubkey,
+    pub admin: Pubkey,
+    pub arbitrator: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub min_wager: u64,
+    pub max_wager: u64,
+    pub min_claim_window: u64,
+    pub min_dispute_window: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub arbitrator: Pubkey,
+    pub fee_bps: u16,
+    pub min_wager: u64,
+    pub max_wager: u64,
+    pub min_claim_window: u64,
+    pub min_dispute_window: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseFlagsUpdated {
+    pub pause: PauseFlags,
+    pub claims_paused_for: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetStateMigrated {
+    pub legacy_bet_state: Pubkey,
+    pub bet_state: Pubkey,
+    pub running_total_pool: u64,
+    pub claim_deadline: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WagerDetailMigrated {
+    pub legacy_wager_detail: Pubkey,
+    pub wager_detail: Pubkey,
+    pub bettor: Pubkey,
+    pub party: PartyStatus,
+    pub bet_value: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserAccountMigrated {
+    pub legacy_user_account: Pubkey,
+    pub user_account: Pubkey,
+    pub account_owner: Pubkey,
+    pub current_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountLayoutUpgraded {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
 }
 
 
@@ -631,6 +4518,8 @@ pub enum ErrorCode {
     InvalidBetRange,
     #[msg("Cannot carry out this action. You are not a part of the winning party for this bet.")]
     NonWinningParty,
+    #[msg("Cannot carry out this action. You are a part of the winning party for this bet.")]
+    NonLosingParty,
     #[msg("Cannot carry out this action until all funds are withdrawn.")]
     FundsStillInPlay,
     #[msg("Cannot carry out this action while the bet is still open.")]
@@ -639,29 +4528,169 @@ pub enum ErrorCode {
     BetStillUndecided,
     #[msg("Cannot carry out this action when bet's outcome has already been decided.")]
     BetAlreadyDecided,
+    #[msg("Cannot carry out this action because the bet was voided. Refund your wager instead.")]
+    BetIsVoid,
+    #[msg("Wagers can only be refunded from a voided bet.")]
+    BetNotVoid,
+    #[msg("Remaining accounts must be passed as (wager detail, user account, account vault, bettor) quadruples.")]
+    InvalidRemainingAccounts,
+    #[msg("The wager is too small to cover the take fee.")]
+    WagerTooSmall,
     #[msg("Cannot carry out this action. You are not the creator of this bet.")]
     NotBetCreator,
+    #[msg("Cannot carry out this action. You are not the resolver of this bet.")]
+    NotBetResolver,
+    #[msg("Cannot carry out this action. You are not the admin of this bet.")]
+    NotBetAdmin,
+    #[msg("Cannot carry out this action. You are not the arbitrator of this bet.")]
+    NotBetArbitrator,
+    #[msg("The arbitrator cannot also be the creator or resolver of the bet.")]
+    ArbitratorNotIndependent,
+    #[msg("An outcome has already been proposed for this bet.")]
+    OutcomeAlreadyProposed,
+    #[msg("There is no undisputed outcome proposal for this bet.")]
+    OutcomeNotProposed,
+    #[msg("There is no disputed outcome proposal for this bet.")]
+    OutcomeNotDisputed,
+    #[msg("Cannot carry out this action until the bet's outcome is finalized.")]
+    OutcomeNotFinalized,
+    #[msg("The dispute window for this outcome proposal has closed.")]
+    DisputeWindowClosed,
+    #[msg("The outcome proposal can still be disputed.")]
+    DisputeWindowStillOpen,
+    #[msg("The given account did not post the bond for this outcome proposal.")]
+    InvalidBondRecipient,
     #[msg("Cannot carry out this action when the bet is closed or is already settled.")]
     BetIsClosedOrSettled,
     #[msg("You cannot place a wager from this account because you are not the account owner.")]
     NotAccountOwnerToPlaceWager,
     #[msg("You cannot claim winnings for this account because you are not the account owner.")]
     NotAccountOwnerToClaimWinnings,
+    #[msg("You cannot cancel a wager from this account because you are not the account owner.")]
+    NotAccountOwnerToCancelWager,
+    #[msg("You cannot acknowledge a loss for this account because you are not the account owner.")]
+    NotAccountOwnerToAcknowledgeLoss,
+    #[msg("You cannot refund a wager to this account because you are not the account owner.")]
+    NotAccountOwnerToRefundWager,
+    #[msg("This wager does not belong to the given bet.")]
+    WagerBetStateMismatch,
+    #[msg("This wager does not belong to the given bettor.")]
+    WagerBettorMismatch,
     #[msg("Could not find that wager within your list of active wagers.")]
     ActiveWagerNotFound,
     #[msg("You do not have any active bets open.")]
     ActiveWagersEmpty,
+    #[msg("You have reached the maximum number of active wagers. Claim or cancel a wager first.")]
+    ActiveWagerLimitReached,
+    #[msg("Could not calculate the payout for this wager.")]
+    PayoutCalculationError,
+    #[msg("The amount is too large to calculate with.")]
+    MathOverflow,
+    #[msg("Cannot carry out this action before the bet's start time.")]
+    BetNotStarted,
+    #[msg("Cannot carry out this action after the bet's end time.")]
+    BettingWindowEnded,
+    #[msg("Cannot close the bet before its end time.")]
+    BetNotEnded,
+    #[msg("Cannot carry out this action until the bet is settled.")]
+    BetNotSettled,
+    #[msg("A settled bet can only be closed after its retention period.")]
+    RetentionPeriodNotOver,
+    #[msg("Every wager on this bet has to be claimed, refunded or settled as a loss first.")]
+    PositionsStillOpen,
+    #[msg("The bet's market vault has to be closed first.")]
+    MarketVaultStillOpen,
+    #[msg("The claim window for this bet has expired.")]
+    ClaimWindowExpired,
+    #[msg("Unclaimed funds can only be swept after the claim deadline.")]
+    ClaimWindowStillOpen,
+    #[msg("Only the bet creator or the treasury authority can sweep unclaimed funds.")]
+    NotSweepAuthority,
+    #[msg("The given account is not the Pyth price feed for this bet.")]
+    InvalidPriceFeed,
+    #[msg("The price feed is not currently publishing a trading price.")]
+    PriceUnavailable,
+    #[msg("The price feed returned a price that cannot be used to resolve this bet.")]
+    InvalidOraclePrice,
+    #[msg("The price feed has not published a price since the bet ended.")]
+    PriceBeforeEndTime,
+    #[msg("The price was published too long after the bet ended, its outcome has to be proposed instead.")]
+    PriceAfterResolutionWindow,
+    #[msg("The price feed's exponent does not match the bet's snapshot price.")]
+    PriceExponentMismatch,
+    #[msg("The price feed's confidence interval is too wide to resolve this bet.")]
+    PriceTooUncertain,
 
     // User Account Errors
     #[msg("You are not the creator of this account.")]
     InvalidAccountOwner,
     #[msg("You must withdraw all of your betting funds to close your account.")]
     AccountBalanceNotEmpty,
+    #[msg("You must claim, cancel or settle all of your wagers to close your account.")]
+    ActiveWagersRemaining,
     #[msg("Invalid desposit amount.")]
     InvalidDepositAmount,
     #[msg("Invalid withdrawal amount.")]
     InvalidWithdrawalAmount,
     #[msg("Cannot withdraw from an empty account.")]
     CannotWithdrawFromEmptyAccount,
+    #[msg("The account does not hold enough lamports for this action.")]
+    InsufficientLamports,
+    #[msg("Your account balance is too low for this wager.")]
+    InsufficientAccountBalance,
+
+    // Treasury Errors
+    #[msg("You are not the authority of the treasury.")]
+    NotTreasuryAuthority,
+    #[msg("The given account is not the treasury set in the config.")]
+    InvalidTreasury,
+
+    // Config Errors
+    #[msg("You are not the admin of the config.")]
+    NotConfigAdmin,
+    #[msg("Only the program's upgrade authority can carry out this action.")]
+    NotUpgradeAuthority,
+    #[msg("The fee rate cannot be more than 10000 basis points.")]
+    InvalidFeeRate,
+    #[msg("The minimum wager cannot be more than the maximum wager.")]
+    InvalidWagerLimits,
+    #[msg("The minimum claim window must be at least one second.")]
+    InvalidClaimWindow,
+    #[msg("The claim window is shorter than the minimum claim window in the config.")]
+    ClaimWindowTooShort,
+    #[msg("The minimum dispute window must be at least one second.")]
+    InvalidDisputeWindow,
+    #[msg("The dispute window is shorter than the minimum dispute window in the config.")]
+    DisputeWindowTooShort,
+    #[msg("This instruction is paused by the config admin.")]
+    ProgramPaused,
+    #[msg("The wager is below the minimum wager.")]
+    WagerBelowMinimum,
+    #[msg("The wager is above the maximum wager.")]
+    WagerAboveMaximum,
+
+    // Account Layout Errors
+    #[msg("The given account is not a v1 account of the expected type.")]
+    InvalidLegacyAccount,
+    #[msg("The given symbol, start time or bet range does not match the bet state being migrated.")]
+    MigrationSeedsMismatch,
+    #[msg("A decided v1 bet that has already paid out winnings cannot be migrated.")]
+    LegacyPayoutsStarted,
+    #[msg("The given account is not a versioned bet state, wager detail or user account.")]
+    InvalidLayoutAccount,
+    #[msg("The account already has the current layout.")]
+    LayoutAlreadyCurrent,
+    #[msg("The account's layout version cannot be upgraded by this program.")]
+    UnsupportedLayoutVersion,
+    #[msg("The symbol is longer than 32 bytes.")]
+    SymbolTooLong,
+
+    // Token Collateral Errors
+    #[msg("This instruction does not match the collateral the bet is settled in.")]
+    CollateralMismatch,
+
+    // Lamport Vault Errors
+    #[msg("The vault does not belong to this account.")]
+    InvalidVault,
 
 }
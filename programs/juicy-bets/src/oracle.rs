@@ -0,0 +1,380 @@
+use anchor_lang::prelude::*;
+
+use crate::{BetOutcomeStatus, BetRange, ErrorCode};
+
+// Pyth oracle program on devnet, which is where this program is deployed (see Anchor.toml)
+pub mod pyth_program {
+    use anchor_lang::declare_id;
+    declare_id!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+}
+
+// Pyth v2 price account layout, only the fields we read
+const MAGIC: u32 = 0xa1b2_c3d4;
+const VERSION_2: u32 = 2;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const PRICE_STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPO_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+
+// Everything up to and including the aggregate price info
+pub const PRICE_ACCOUNT_HEADER_SIZE: usize = 240;
+
+// How long after a market's end time a published price can still resolve it: 5 minutes. Past that the
+// outcome has to go through a proposal instead.
+pub const MAX_RESOLUTION_DELAY: u64 = 5 * 60;
+
+// Widest confidence interval a resolving price may have, in basis points of the price. Half of the
+// narrowest band, so an uncertain quote cannot decide a market.
+pub const MAX_CONF_BPS: u64 = 50;
+
+// Aggregate price read from a Pyth price account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    // Parses the aggregate price out of raw Pyth price account data, rejecting anything that
+    // is not a v2 price account or whose aggregate is not currently trading
+    pub fn from_account_data(data: &[u8]) -> Result<OraclePrice> {
+        check_price_account(data)?;
+
+        if read_u32(data, AGG_STATUS_OFFSET) != PRICE_STATUS_TRADING {
+            return Err(error!(ErrorCode::PriceUnavailable));
+        }
+
+        Ok(OraclePrice {
+            price: read_i64(data, AGG_PRICE_OFFSET),
+            conf: read_u64(data, AGG_CONF_OFFSET),
+            expo: read_u32(data, EXPO_OFFSET) as i32,
+            publish_time: read_i64(data, TIMESTAMP_OFFSET),
+        })
+    }
+
+    pub fn from_account_info(price_feed: &AccountInfo) -> Result<OraclePrice> {
+        check_price_feed_owner(price_feed)?;
+
+        let data = price_feed.try_borrow_data()?;
+        OraclePrice::from_account_data(&data)
+    }
+}
+
+// Exponent of a Pyth price account, read when a market is created so its snapshot price can be checked
+// against the resolving price. The feed does not have to be trading yet.
+pub fn price_feed_expo(price_feed: &AccountInfo) -> Result<i32> {
+    check_price_feed_owner(price_feed)?;
+
+    let data = price_feed.try_borrow_data()?;
+    check_price_account(&data)?;
+
+    Ok(read_u32(&data, EXPO_OFFSET) as i32)
+}
+
+fn check_price_feed_owner(price_feed: &AccountInfo) -> Result<()> {
+    if *price_feed.owner != pyth_program::ID {
+        return Err(error!(ErrorCode::InvalidPriceFeed));
+    }
+
+    Ok(())
+}
+
+fn check_price_account(data: &[u8]) -> Result<()> {
+    if data.len() < PRICE_ACCOUNT_HEADER_SIZE
+        || read_u32(data, MAGIC_OFFSET) != MAGIC
+        || read_u32(data, VERSION_OFFSET) != VERSION_2
+        || read_u32(data, ACCOUNT_TYPE_OFFSET) != ACCOUNT_TYPE_PRICE
+    {
+        return Err(error!(ErrorCode::InvalidPriceFeed));
+    }
+
+    Ok(())
+}
+
+impl BetRange {
+    // Band edges in basis points of price movement, lower bound inclusive and upper bound exclusive
+    pub fn bounds_bps(&self) -> (Option<i128>, Option<i128>) {
+        match self {
+            BetRange::NegativeThreeAndOver => (None, Some(-300)),
+            BetRange::NegativeTwoToThree => (Some(-300), Some(-200)),
+            BetRange::NegativeOneToTwo => (Some(-200), Some(-100)),
+            BetRange::NegativeOneToZero => (Some(-100), Some(0)),
+            BetRange::ZeroToPositiveOne => (Some(0), Some(100)),
+            BetRange::PositiveOneToTwo => (Some(100), Some(200)),
+            BetRange::PositiveTwoToThree => (Some(200), Some(300)),
+            BetRange::PositiveThreeAndOver => (Some(300), None),
+        }
+    }
+
+    // Whether the move from snapshot_price to price falls inside this band. Both prices must use the
+    // same exponent; the comparison is done on cross-multiplied integers so nothing is rounded.
+    pub fn contains(&self, snapshot_price: u128, price: i64) -> Result<bool> {
+        if snapshot_price == 0 || snapshot_price > i64::MAX as u128 || price <= 0 {
+            return Err(error!(ErrorCode::InvalidOraclePrice));
+        }
+
+        let snapshot_price = snapshot_price as i128;
+        let scaled_move = (price as i128 - snapshot_price) * 10_000;
+        let (lower, upper) = self.bounds_bps();
+
+        let above_lower = match lower {
+            Some(bps) => scaled_move >= bps * snapshot_price,
+            None => true,
+        };
+        let below_upper = match upper {
+            Some(bps) => scaled_move < bps * snapshot_price,
+            None => true,
+        };
+
+        Ok(above_lower && below_upper)
+    }
+}
+
+// Party one backs the price landing in the market's band and party two bets against it. The price has
+// to have been published at or shortly after the market's end time so nobody can resolve with a stale or
+// a much later quote, in the snapshot's exponent, and with a tight enough confidence interval.
+pub fn resolve_outcome(
+    bet_range: BetRange,
+    snapshot_price: u128,
+    snapshot_expo: i32,
+    end_time: u64,
+    oracle_price: &OraclePrice,
+) -> Result<BetOutcomeStatus> {
+    if oracle_price.publish_time < 0 || (oracle_price.publish_time as u64) < end_time {
+        return Err(error!(ErrorCode::PriceBeforeEndTime));
+    }
+
+    let latest_publish_time = end_time.checked_add(MAX_RESOLUTION_DELAY).ok_or(ErrorCode::MathOverflow)?;
+    if oracle_price.publish_time as u64 > latest_publish_time {
+        return Err(error!(ErrorCode::PriceAfterResolutionWindow));
+    }
+
+    if oracle_price.expo != snapshot_expo {
+        return Err(error!(ErrorCode::PriceExponentMismatch));
+    }
+
+    // Non-positive prices are rejected by the band check below
+    if oracle_price.price > 0
+        && oracle_price.conf as u128 * 10_000 > oracle_price.price as u128 * MAX_CONF_BPS as u128
+    {
+        return Err(error!(ErrorCode::PriceTooUncertain));
+    }
+
+    if bet_range.contains(snapshot_price, oracle_price.price)? {
+        Ok(BetOutcomeStatus::PartyOneWin)
+    } else {
+        Ok(BetOutcomeStatus::PartyTwoWin)
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    read_u64(data, offset) as i64
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+
+    // Builds raw price account data in the Pyth v2 layout so resolution can be exercised without a cluster
+    pub struct MockPriceAccount {
+        pub price: i64,
+        pub conf: u64,
+        pub expo: i32,
+        pub publish_time: i64,
+        pub status: u32,
+    }
+
+    impl MockPriceAccount {
+        pub fn trading(price: i64, publish_time: i64) -> MockPriceAccount {
+            MockPriceAccount { price, conf: 0, expo: -3, publish_time, status: PRICE_STATUS_TRADING }
+        }
+
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let mut data = vec![0u8; 3312];
+            data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&MAGIC.to_le_bytes());
+            data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&VERSION_2.to_le_bytes());
+            data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4].copy_from_slice(&ACCOUNT_TYPE_PRICE.to_le_bytes());
+            data[EXPO_OFFSET..EXPO_OFFSET + 4].copy_from_slice(&self.expo.to_le_bytes());
+            data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&self.publish_time.to_le_bytes());
+            data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].copy_from_slice(&self.price.to_le_bytes());
+            data[AGG_CONF_OFFSET..AGG_CONF_OFFSET + 8].copy_from_slice(&self.conf.to_le_bytes());
+            data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4].copy_from_slice(&self.status.to_le_bytes());
+            data
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockPriceAccount;
+    use super::*;
+
+    fn error_code<T>(result: Result<T>) -> u32 {
+        match result {
+            Err(Error::AnchorError(error)) => error.error_code_number,
+            _ => panic!("expected an anchor error"),
+        }
+    }
+
+    #[test]
+    fn pyth_program_id_matches_devnet_address() {
+        assert_eq!(pyth_program::ID.to_string(), "gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
+    }
+
+    #[test]
+    fn parses_mock_price_account() {
+        let data = MockPriceAccount::trading(725_450, 1_650_000_000).to_bytes();
+        let price = OraclePrice::from_account_data(&data).unwrap();
+
+        assert_eq!(price.price, 725_450);
+        assert_eq!(price.expo, -3);
+        assert_eq!(price.publish_time, 1_650_000_000);
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_halted_prices() {
+        let mut data = MockPriceAccount::trading(725_450, 1_650_000_000).to_bytes();
+        data[0] = 0;
+        assert!(OraclePrice::from_account_data(&data).is_err());
+
+        let mut halted = MockPriceAccount::trading(725_450, 1_650_000_000);
+        halted.status = 0;
+        assert!(OraclePrice::from_account_data(&halted.to_bytes()).is_err());
+
+        assert!(OraclePrice::from_account_data(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn reads_the_exponent_of_a_halted_feed_only_from_pyth() {
+        let key = Pubkey::new_unique();
+        let mut halted = MockPriceAccount::trading(725_450, 1_650_000_000);
+        halted.status = 0;
+        let mut data = halted.to_bytes();
+        let mut lamports = 0;
+
+        let price_feed = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &pyth_program::ID, false, 0);
+        assert_eq!(price_feed_expo(&price_feed).unwrap(), -3);
+
+        let other_owner = Pubkey::new_unique();
+        let mut data = halted.to_bytes();
+        let mut lamports = 0;
+
+        let price_feed = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &other_owner, false, 0);
+        assert_eq!(error_code(price_feed_expo(&price_feed)), u32::from(ErrorCode::InvalidPriceFeed));
+    }
+
+    #[test]
+    fn bet_range_band_edges() {
+        // snapshot of 100.000 with expo -3
+        let snapshot = 100_000u128;
+
+        assert!(BetRange::ZeroToPositiveOne.contains(snapshot, 100_000).unwrap());
+        assert!(BetRange::ZeroToPositiveOne.contains(snapshot, 100_999).unwrap());
+        assert!(!BetRange::ZeroToPositiveOne.contains(snapshot, 101_000).unwrap());
+        assert!(BetRange::PositiveOneToTwo.contains(snapshot, 101_000).unwrap());
+
+        assert!(BetRange::NegativeOneToZero.contains(snapshot, 99_999).unwrap());
+        assert!(BetRange::NegativeOneToZero.contains(snapshot, 99_000).unwrap());
+        assert!(BetRange::NegativeOneToTwo.contains(snapshot, 98_999).unwrap());
+
+        assert!(BetRange::NegativeThreeAndOver.contains(snapshot, 96_999).unwrap());
+        assert!(!BetRange::NegativeThreeAndOver.contains(snapshot, 97_000).unwrap());
+        assert!(BetRange::NegativeTwoToThree.contains(snapshot, 97_000).unwrap());
+
+        assert!(BetRange::PositiveThreeAndOver.contains(snapshot, 103_000).unwrap());
+        assert!(BetRange::PositiveThreeAndOver.contains(snapshot, i64::MAX).unwrap());
+    }
+
+    #[test]
+    fn bet_range_rejects_non_positive_prices() {
+        assert!(BetRange::ZeroToPositiveOne.contains(0, 100).is_err());
+        assert!(BetRange::ZeroToPositiveOne.contains(100, 0).is_err());
+        assert!(BetRange::ZeroToPositiveOne.contains(100, -5).is_err());
+    }
+
+    #[test]
+    fn resolves_outcome_from_mock_price() {
+        let end_time = 1_650_000_000u64;
+        let in_band = OraclePrice::from_account_data(&MockPriceAccount::trading(101_500, end_time as i64).to_bytes()).unwrap();
+        let out_of_band = OraclePrice::from_account_data(&MockPriceAccount::trading(98_000, end_time as i64 + 60).to_bytes()).unwrap();
+
+        assert_eq!(
+            resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &in_band).unwrap(),
+            BetOutcomeStatus::PartyOneWin
+        );
+        assert_eq!(
+            resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &out_of_band).unwrap(),
+            BetOutcomeStatus::PartyTwoWin
+        );
+    }
+
+    #[test]
+    fn rejects_prices_published_before_end_time() {
+        let end_time = 1_650_000_000u64;
+        let stale = OraclePrice::from_account_data(&MockPriceAccount::trading(101_500, end_time as i64 - 1).to_bytes()).unwrap();
+
+        assert!(resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &stale).is_err());
+    }
+
+    #[test]
+    fn rejects_prices_published_after_the_resolution_window() {
+        let end_time = 1_650_000_000u64;
+        let latest = MockPriceAccount::trading(101_500, (end_time + MAX_RESOLUTION_DELAY) as i64);
+        let late = MockPriceAccount::trading(101_500, (end_time + MAX_RESOLUTION_DELAY) as i64 + 1);
+
+        let latest = OraclePrice::from_account_data(&latest.to_bytes()).unwrap();
+        assert!(resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &latest).is_ok());
+
+        let late = OraclePrice::from_account_data(&late.to_bytes()).unwrap();
+        assert_eq!(
+            error_code(resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &late)),
+            u32::from(ErrorCode::PriceAfterResolutionWindow)
+        );
+    }
+
+    #[test]
+    fn rejects_wide_confidence_and_mismatched_exponents() {
+        let end_time = 1_650_000_000u64;
+
+        // 50 bps of 101.500 is 0.50750
+        let mut widest = MockPriceAccount::trading(101_500, end_time as i64);
+        widest.conf = 507;
+        let widest = OraclePrice::from_account_data(&widest.to_bytes()).unwrap();
+        assert!(resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &widest).is_ok());
+
+        let mut too_wide = MockPriceAccount::trading(101_500, end_time as i64);
+        too_wide.conf = 508;
+        let too_wide = OraclePrice::from_account_data(&too_wide.to_bytes()).unwrap();
+        assert_eq!(
+            error_code(resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -3, end_time, &too_wide)),
+            u32::from(ErrorCode::PriceTooUncertain)
+        );
+
+        let in_band = OraclePrice::from_account_data(&MockPriceAccount::trading(101_500, end_time as i64).to_bytes()).unwrap();
+        assert_eq!(
+            error_code(resolve_outcome(BetRange::PositiveOneToTwo, 100_000, -5, end_time, &in_band)),
+            u32::from(ErrorCode::PriceExponentMismatch)
+        );
+    }
+}
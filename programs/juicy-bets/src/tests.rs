@@ -0,0 +1,1742 @@
+// Account validation and handler tests. Each test builds the accounts for an instruction by hand and runs them
+// through the generated try_accounts, so the constraints are exercised without a validator. Handler tests then
+// run the instruction itself against those accounts, with the system and token program transfers it makes
+// carried out by the syscall stubs.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::sync::Once;
+
+use anchor_lang::solana_program::entrypoint::{ProgramResult, SUCCESS};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+
+use super::*;
+
+thread_local! {
+    // Cluster time returned by Clock::get() on the test's thread
+    static NOW: Cell<i64> = const { Cell::new(0) };
+}
+
+fn set_clock(unix_timestamp: i64) {
+    NOW.with(|now| now.set(unix_timestamp));
+}
+
+// Stands in for the rent and clock sysvars and for the only cross-program calls the program makes: system
+// program and token program transfers and token account closes. A transfer or close has to be signed by the
+// account it debits, directly or through the seeds of a PDA of this program, and the system program only
+// debits accounts it owns that hold no data, so a vault never pays out of an account it does not control.
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Clock) = Clock { unix_timestamp: NOW.with(|now| now.get()), ..Clock::default() };
+        }
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(&self, instruction: &Instruction, account_infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+        let account = |index: usize| {
+            account_infos
+                .iter()
+                .find(|info| *info.key == instruction.accounts[index].pubkey)
+                .expect("every account of a CPI is passed along with it")
+        };
+        let signed = |info: &AccountInfo| {
+            info.is_signer || signers_seeds.iter().any(|seeds| Pubkey::create_program_address(seeds, &crate::ID) == Ok(*info.key))
+        };
+
+        if instruction.program_id == system_program::ID {
+            // SystemInstruction::Transfer, a u32 tag of 2 followed by the lamports
+            assert_eq!(instruction.data[..4], 2u32.to_le_bytes());
+            let lamports = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let (from, to) = (account(0), account(1));
+
+            if !signed(from) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if *from.owner != system_program::ID || !from.data_is_empty() {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            **from.try_borrow_mut_lamports()? = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+            **to.try_borrow_mut_lamports()? += lamports;
+        } else if instruction.program_id == token::ID && instruction.data[0] == 9 {
+            // TokenInstruction::CloseAccount, only empty token accounts can be closed
+            let (account, destination, authority) = (account(0), account(1), account(2));
+
+            if !signed(authority) || account.try_borrow_data()?[32..64] != authority.key.to_bytes() {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if token_amount(&account.try_borrow_data()?) != 0 {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            **destination.try_borrow_mut_lamports()? += account.lamports();
+            **account.try_borrow_mut_lamports()? = 0;
+            account.try_borrow_mut_data()?.fill(0);
+        } else if instruction.program_id == token::ID {
+            // TokenInstruction::Transfer, a tag of 3 followed by the amount
+            assert_eq!(instruction.data[0], 3);
+            let amount = u64::from_le_bytes(instruction.data[1..9].try_into().unwrap());
+            let (from, to, authority) = (account(0), account(1), account(2));
+
+            if !signed(authority) || from.try_borrow_data()?[32..64] != authority.key.to_bytes() {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let from_amount = token_amount(&from.try_borrow_data()?).checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+            let to_amount = token_amount(&to.try_borrow_data()?) + amount;
+            from.try_borrow_mut_data()?[64..72].copy_from_slice(&from_amount.to_le_bytes());
+            to.try_borrow_mut_data()?[64..72].copy_from_slice(&to_amount.to_le_bytes());
+        } else {
+            panic!("unexpected CPI to {}", instruction.program_id);
+        }
+
+        Ok(())
+    }
+}
+
+static SET_STUBS: Once = Once::new();
+
+fn set_syscall_stubs() {
+    SET_STUBS.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+}
+
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    executable: bool,
+}
+
+impl TestAccount {
+    fn program_account<T: AccountSerialize>(key: Pubkey, account: &T, space: usize) -> TestAccount {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+
+        TestAccount { key, owner: crate::ID, lamports: 1_000_000_000, data, is_signer: false, executable: false }
+    }
+
+    fn wallet(key: Pubkey) -> TestAccount {
+        TestAccount { key, owner: system_program::ID, lamports: 1_000_000_000, data: Vec::new(), is_signer: true, executable: false }
+    }
+
+    fn lamport_vault(prefix: &[u8], account: &Pubkey) -> TestAccount {
+        let (key, _) = Pubkey::find_program_address(&[prefix, account.as_ref()], &crate::ID);
+
+        TestAccount { key, owner: system_program::ID, lamports: Rent::default().minimum_balance(0), data: Vec::new(), is_signer: false, executable: false }
+    }
+
+    // SPL token account for mint, owned by owner, holding amount
+    fn token_account(key: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) -> TestAccount {
+        let mut data = vec![0; TokenAccount::LEN];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        // AccountState::Initialized
+        data[108] = 1;
+
+        TestAccount { key, owner: token::ID, lamports: Rent::default().minimum_balance(TokenAccount::LEN), data, is_signer: false, executable: false }
+    }
+
+    fn system_program() -> TestAccount {
+        TestAccount { key: system_program::ID, owner: Pubkey::default(), lamports: 1, data: Vec::new(), is_signer: false, executable: false }
+    }
+
+    fn token_program() -> TestAccount {
+        TestAccount { key: token::ID, owner: Pubkey::default(), lamports: 1, data: Vec::new(), is_signer: false, executable: true }
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, self.is_signer, true, &mut self.lamports, &mut self.data, &self.owner, self.executable, 0)
+    }
+
+    fn read<T: AccountDeserialize>(&self) -> T {
+        T::try_deserialize(&mut &self.data[..]).unwrap()
+    }
+
+    fn write<T: AccountSerialize>(&mut self, account: &T) {
+        let mut data: &mut [u8] = &mut self.data;
+        account.try_serialize(&mut data).unwrap();
+    }
+
+    // Lamports a vault holds on top of its rent-exempt minimum
+    fn vault_balance(&self) -> u64 {
+        self.lamports - Rent::default().minimum_balance(0)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.lamports == 0
+    }
+}
+
+fn token_amount(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[64..72].try_into().unwrap())
+}
+
+// Runs an instruction the way the program's entrypoint does: validate the accounts, run the handler and
+// persist the accounts it changed
+fn run_instruction<'info, T: Accounts<'info> + AccountsExit<'info>>(
+    accounts: &[AccountInfo<'info>],
+    remaining_accounts: &[AccountInfo<'info>],
+    ix_data: &[u8],
+    handler: fn(Context<'_, '_, '_, 'info, T>) -> Result<()>,
+) -> Result<()> {
+    set_syscall_stubs();
+
+    let mut bumps = BTreeMap::new();
+    let mut instruction_accounts = T::try_accounts(&crate::ID, &mut &accounts[..], ix_data, &mut bumps)?;
+
+    handler(Context::new(&crate::ID, &mut instruction_accounts, remaining_accounts, bumps))?;
+
+    instruction_accounts.exit(&crate::ID)
+}
+
+fn bet_state(symbol: &str, status: BetStateStatus, bet_outcome: BetOutcomeStatus) -> TestAccount {
+    let start_time = 1_650_000_000u64;
+    let bet_range = BetRange::ZeroToPositiveOne;
+    let (key, bump) = Pubkey::find_program_address(
+        &[b"bet", symbol.as_bytes(), &start_time.to_le_bytes(), &[bet_range as u8]],
+        &crate::ID,
+    );
+    let creator = Pubkey::new_unique();
+
+    let bet_state = BetState {
+        version: BetState::CURRENT_VERSION,
+        symbol: symbol.to_string(),
+        creator,
+        resolver: creator,
+        admin: creator,
+        arbitrator: Pubkey::new_unique(),
+        dispute_window: 60 * 60,
+        resolution: OutcomeResolution::Finalized,
+        proposed_outcome: bet_outcome,
+        proposer: creator,
+        proposed_at: start_time as i64 + 120,
+        disputer: Pubkey::default(),
+        running_total_pool: 3_000,
+        static_total_pool: 3_000,
+        party_one_pool: 1_000,
+        party_two_pool: 2_000,
+        start_price: 0,
+        bet_outcome,
+        status,
+        start_time,
+        end_time: start_time + 120,
+        // Finalized as soon as it ended
+        claim_window: 7 * 24 * 60 * 60,
+        claim_deadline: start_time + 120 + 7 * 24 * 60 * 60,
+        claims_paused_offset: 0,
+        snapshot_price: 725_450,
+        snapshot_expo: -3,
+        bet_range,
+        winning_pool_claimed: 0,
+        total_paid_out: 0,
+        total_fees: 0,
+        rounding_dust: 0,
+        settled_at: 0,
+        unclaimed_swept: 0,
+        price_feed: Pubkey::new_unique(),
+        bump,
+        collateral_mint: Pubkey::default(),
+        migrated_from: Pubkey::default(),
+        open_positions: 2,
+    };
+
+    TestAccount::program_account(key, &bet_state, BetState::MAX_SIZE + 8)
+}
+
+fn wager_detail(bet_state: &Pubkey, bettor: &Pubkey, party: PartyStatus, bet_value: u64) -> TestAccount {
+    let (key, bump) = Pubkey::find_program_address(
+        &[b"wager", bet_state.as_ref(), bettor.as_ref(), &[party.number()]],
+        &crate::ID,
+    );
+
+    let wager_detail = WagerDetail {
+        version: WagerDetail::CURRENT_VERSION,
+        bettor: *bettor,
+        bet_state: *bet_state,
+        party,
+        bet_value,
+        bump,
+    };
+
+    TestAccount::program_account(key, &wager_detail, WagerDetail::MAX_SIZE + 8)
+}
+
+fn user_account(owner: &Pubkey, active_wagers: Vec<Pubkey>) -> TestAccount {
+    let (key, bump) = Pubkey::find_program_address(&[b"user", owner.as_ref()], &crate::ID);
+
+    let user_account = UserAccount {
+        version: UserAccount::CURRENT_VERSION,
+        account_owner: *owner,
+        wins: 0,
+        losses: 0,
+        realized_profit: 0,
+        active_wagers,
+        current_balance: 10_000,
+        bump,
+    };
+
+    TestAccount::program_account(key, &user_account, UserAccount::INITIAL_SIZE + 8)
+}
+
+fn treasury() -> TestAccount {
+    let (key, bump) = Pubkey::find_program_address(&[b"treasury"], &crate::ID);
+
+    let treasury = Treasury { authority: Pubkey::new_unique(), total_fees_collected: 0, total_withdrawn: 0, total_swept: 0, bump };
+
+    TestAccount::program_account(key, &treasury, Treasury::MAX_SIZE + 8)
+}
+
+fn config(treasury: &Pubkey, pause: PauseFlags) -> TestAccount {
+    let (key, bump) = Pubkey::find_program_address(&[b"config"], &crate::ID);
+
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 200,
+        treasury: *treasury,
+        min_wager: 0,
+        max_wager: u64::MAX,
+        pause,
+        bump,
+        min_claim_window: 60 * 60,
+        min_dispute_window: 60 * 60,
+        claims_paused_at: 0,
+        claims_paused_for: 0,
+        arbitrator: Pubkey::new_unique(),
+    };
+
+    TestAccount::program_account(key, &config, Config::MAX_SIZE + 8)
+}
+
+fn error_code<T>(result: Result<T>) -> u32 {
+    match result {
+        Ok(_) => panic!("expected the accounts to be rejected"),
+        Err(Error::AnchorError(error)) => error.error_code_number,
+        Err(Error::ProgramError(error)) => panic!("expected an anchor error, got {:?}", error.program_error),
+    }
+}
+
+// Two markets, the first with a wager of 1_000 on party one from the bettor and a wager of 2_000 on party two
+// from another bettor, which make up its pools. Every vault holds the funds its account tracks.
+struct WagerFixture {
+    bet_state: TestAccount,
+    bet_vault: TestAccount,
+    wager_detail: TestAccount,
+    other_market_wager_detail: TestAccount,
+    other_bettor_wager_detail: TestAccount,
+    user_account: TestAccount,
+    account_vault: TestAccount,
+    other_user_account: TestAccount,
+    other_account_vault: TestAccount,
+    bettor: TestAccount,
+    treasury: TestAccount,
+    config: TestAccount,
+}
+
+impl WagerFixture {
+    fn new(status: BetStateStatus, bet_outcome: BetOutcomeStatus) -> WagerFixture {
+        // Halfway through the betting window, long before the claim deadline
+        set_clock(1_650_000_060);
+
+        let bet_state = bet_state("Crypto.SOL/USD", status, bet_outcome);
+        let other_bet_state = self::bet_state("Crypto.BTC/USD", status, bet_outcome);
+        let bettor = TestAccount::wallet(Pubkey::new_unique());
+        let other_bettor = Pubkey::new_unique();
+
+        let wager_detail = wager_detail(&bet_state.key, &bettor.key, PartyStatus::PartyOne, 1_000);
+        let other_market_wager_detail = self::wager_detail(&other_bet_state.key, &bettor.key, PartyStatus::PartyOne, 1_000);
+        let other_bettor_wager_detail = self::wager_detail(&bet_state.key, &other_bettor, PartyStatus::PartyTwo, 2_000);
+        let treasury = treasury();
+        let user_account = user_account(&bettor.key, vec![wager_detail.key, other_market_wager_detail.key]);
+        let other_user_account = self::user_account(&other_bettor, vec![other_bettor_wager_detail.key]);
+
+        let mut bet_vault = TestAccount::lamport_vault(b"bet_vault", &bet_state.key);
+        let mut account_vault = TestAccount::lamport_vault(b"account_vault", &user_account.key);
+        let mut other_account_vault = TestAccount::lamport_vault(b"account_vault", &other_user_account.key);
+        bet_vault.lamports += bet_state.read::<BetState>().running_total_pool;
+        account_vault.lamports += user_account.read::<UserAccount>().current_balance;
+        other_account_vault.lamports += other_user_account.read::<UserAccount>().current_balance;
+
+        WagerFixture {
+            bet_vault,
+            account_vault,
+            other_account_vault,
+            user_account,
+            other_user_account,
+            bet_state,
+            wager_detail,
+            other_market_wager_detail,
+            other_bettor_wager_detail,
+            bettor,
+            config: config(&treasury.key, PauseFlags::default()),
+            treasury,
+        }
+    }
+
+    fn pause(&mut self, pause: PauseFlags) {
+        self.config = config(&self.treasury.key, pause);
+    }
+}
+
+enum Swap {
+    None,
+    OtherMarketWager,
+    OtherBettorWager,
+    OtherUserAccount,
+    OtherAccountVault,
+}
+
+fn cancel_wager(fixture: &mut WagerFixture, swap: Swap) -> Result<()> {
+    let wager_detail = match swap {
+        Swap::OtherMarketWager => fixture.other_market_wager_detail.info(),
+        Swap::OtherBettorWager => fixture.other_bettor_wager_detail.info(),
+        _ => fixture.wager_detail.info(),
+    };
+    let user_account = match swap {
+        Swap::OtherUserAccount => fixture.other_user_account.info(),
+        _ => fixture.user_account.info(),
+    };
+    let account_vault = match swap {
+        Swap::OtherAccountVault => fixture.other_account_vault.info(),
+        _ => fixture.account_vault.info(),
+    };
+    let mut system_program = TestAccount::system_program();
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        wager_detail,
+        user_account,
+        account_vault,
+        fixture.bettor.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+
+    run_instruction(&accounts, &[], &[], juicy_bets::cancel_wager)
+}
+
+fn claim_winnings(fixture: &mut WagerFixture, swap: Swap) -> Result<()> {
+    let wager_detail = match swap {
+        Swap::OtherMarketWager => fixture.other_market_wager_detail.info(),
+        Swap::OtherBettorWager => fixture.other_bettor_wager_detail.info(),
+        _ => fixture.wager_detail.info(),
+    };
+    let user_account = match swap {
+        Swap::OtherUserAccount => fixture.other_user_account.info(),
+        _ => fixture.user_account.info(),
+    };
+    let account_vault = match swap {
+        Swap::OtherAccountVault => fixture.other_account_vault.info(),
+        _ => fixture.account_vault.info(),
+    };
+    let mut system_program = TestAccount::system_program();
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        wager_detail,
+        user_account,
+        account_vault,
+        fixture.bettor.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+
+    run_instruction(&accounts, &[], &[], juicy_bets::claim_winnings)
+}
+
+// Places 1_020 lamports on party one, 1_000 after the 2% take fee
+fn place_wager(fixture: &mut WagerFixture, swap: Swap) -> Result<()> {
+    let wager_detail = match swap {
+        Swap::OtherMarketWager => fixture.other_market_wager_detail.info(),
+        Swap::OtherBettorWager => fixture.other_bettor_wager_detail.info(),
+        _ => fixture.wager_detail.info(),
+    };
+    let user_account = match swap {
+        Swap::OtherUserAccount => fixture.other_user_account.info(),
+        _ => fixture.user_account.info(),
+    };
+    let account_vault = match swap {
+        Swap::OtherAccountVault => fixture.other_account_vault.info(),
+        _ => fixture.account_vault.info(),
+    };
+    let mut system_program = TestAccount::system_program();
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        wager_detail,
+        user_account,
+        account_vault,
+        fixture.bettor.info(),
+        fixture.config.info(),
+        fixture.treasury.info(),
+        system_program.info(),
+    ];
+    let party = [PartyStatus::PartyOne.number()];
+
+    run_instruction(&accounts, &[], &party, |ctx| juicy_bets::place_wager(ctx, PartyStatus::PartyOne.number(), 1_020))
+}
+
+// Withdraws 1_000 lamports to the bettor
+fn withdraw_from_account(fixture: &mut WagerFixture) -> Result<()> {
+    let mut system_program = TestAccount::system_program();
+    let accounts = [
+        fixture.user_account.info(),
+        fixture.account_vault.info(),
+        fixture.bettor.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+
+    run_instruction(&accounts, &[], &[], |ctx| juicy_bets::withdraw_from_account(ctx, 1_000))
+}
+
+// Each vault holds exactly what its account tracks, before and after a wager moves between them
+fn assert_vaults_match_accounts(fixture: &WagerFixture) {
+    let bet_state = fixture.bet_state.read::<BetState>();
+    let user_account = fixture.user_account.read::<UserAccount>();
+
+    assert_eq!(fixture.bet_vault.vault_balance(), bet_state.running_total_pool);
+    assert_eq!(fixture.account_vault.vault_balance(), user_account.current_balance);
+    assert_eq!(bet_state.running_total_pool, bet_state.party_one_pool + bet_state.party_two_pool);
+}
+
+#[test]
+fn placing_and_cancelling_a_wager_keeps_the_vaults_balanced() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    let treasury_lamports = fixture.treasury.lamports;
+
+    place_wager(&mut fixture, Swap::None).unwrap();
+
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.running_total_pool, 4_000);
+    assert_eq!(bet_state.party_one_pool, 2_000);
+    assert_eq!(fixture.wager_detail.read::<WagerDetail>().bet_value, 2_000);
+    assert_eq!(fixture.user_account.read::<UserAccount>().current_balance, 10_000 - 1_020);
+    assert_eq!(fixture.treasury.lamports, treasury_lamports + 20);
+    assert_eq!(fixture.treasury.read::<Treasury>().total_fees_collected, 20);
+    assert_vaults_match_accounts(&fixture);
+
+    // Cancelling returns the whole position, the stake the bettor already had in it included
+    cancel_wager(&mut fixture, Swap::None).unwrap();
+
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.running_total_pool, 2_000);
+    assert_eq!(bet_state.party_one_pool, 0);
+    assert_eq!(fixture.user_account.read::<UserAccount>().current_balance, 10_000 - 1_020 + 2_000);
+    assert!(fixture.wager_detail.is_closed());
+    assert_vaults_match_accounts(&fixture);
+}
+
+#[test]
+fn cancel_wager_rejects_wager_from_another_market() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+
+    assert_eq!(
+        error_code(cancel_wager(&mut fixture, Swap::OtherMarketWager)),
+        u32::from(ErrorCode::WagerBetStateMismatch)
+    );
+}
+
+#[test]
+fn cancel_wager_rejects_wager_from_another_bettor() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+
+    assert_eq!(
+        error_code(cancel_wager(&mut fixture, Swap::OtherBettorWager)),
+        u32::from(ErrorCode::WagerBettorMismatch)
+    );
+}
+
+#[test]
+fn cancel_wager_rejects_another_bettors_user_account() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+
+    assert_eq!(
+        error_code(cancel_wager(&mut fixture, Swap::OtherUserAccount)),
+        u32::from(ErrorCode::NotAccountOwnerToCancelWager)
+    );
+}
+
+// The proposer says party two won, the disputer says party one, and the arbitrator sides with the disputer.
+// Arbitration takes longer than the claim window, which only starts once the outcome is final.
+#[test]
+fn claim_after_an_arbitrated_dispute_pays_the_winner_and_the_disputer() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::Undecided);
+    let mut proposer = TestAccount::wallet(Pubkey::new_unique());
+    let mut disputer = TestAccount::wallet(Pubkey::new_unique());
+    let mut arbitrator = TestAccount::wallet(fixture.bet_state.read::<BetState>().arbitrator);
+    let disputer_lamports = disputer.lamports;
+
+    let mut bet_state = fixture.bet_state.read::<BetState>();
+    bet_state.resolution = OutcomeResolution::Disputed;
+    bet_state.proposed_outcome = BetOutcomeStatus::PartyTwoWin;
+    bet_state.proposer = proposer.key;
+    bet_state.disputer = disputer.key;
+    bet_state.claim_deadline = 0;
+    fixture.bet_state.write(&bet_state);
+    fixture.bet_vault.lamports += OUTCOME_BOND_LAMPORTS * 2;
+
+    let arbitrated_at = (bet_state.end_time + bet_state.claim_window) as i64 + 60;
+    set_clock(arbitrated_at);
+
+    let mut system_program = TestAccount::system_program();
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        arbitrator.info(),
+        proposer.info(),
+        disputer.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+    run_instruction(&accounts, &[], &[], |ctx| juicy_bets::arbitrate_bet_state_outcome(ctx, 1)).unwrap();
+
+    assert_eq!(disputer.lamports, disputer_lamports + OUTCOME_BOND_LAMPORTS * 2);
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.bet_outcome, BetOutcomeStatus::PartyOneWin);
+    assert_eq!(bet_state.claim_deadline, arbitrated_at as u64 + bet_state.claim_window);
+    assert_vaults_match_accounts(&fixture);
+
+    set_clock(bet_state.claim_deadline as i64);
+    claim_winnings(&mut fixture, Swap::None).unwrap();
+
+    // The only winning stake takes the whole pool
+    let bet_state = fixture.bet_state.read::<BetState>();
+    let user_account = fixture.user_account.read::<UserAccount>();
+    assert_eq!(bet_state.running_total_pool, 0);
+    assert_eq!(bet_state.total_paid_out, 3_000);
+    assert_eq!(fixture.bet_vault.vault_balance(), 0);
+    assert_eq!(user_account.current_balance, 13_000);
+    assert_eq!(user_account.realized_profit, 2_000);
+    assert_eq!(user_account.active_wagers, vec![fixture.other_market_wager_detail.key]);
+    assert_eq!(fixture.account_vault.vault_balance(), 13_000);
+    assert!(fixture.wager_detail.is_closed());
+}
+
+#[test]
+fn sweep_moves_unclaimed_funds_into_the_treasury() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+    let bet_state = fixture.bet_state.read::<BetState>();
+    let mut authority = TestAccount::wallet(bet_state.creator);
+    let treasury_lamports = fixture.treasury.lamports;
+    let mut system_program = TestAccount::system_program();
+
+    let sweep = |fixture: &mut WagerFixture, authority: &mut TestAccount, system_program: &mut TestAccount| {
+        let accounts = [
+            fixture.bet_state.info(),
+            fixture.bet_vault.info(),
+            fixture.treasury.info(),
+            authority.info(),
+            fixture.config.info(),
+            system_program.info(),
+        ];
+        run_instruction(&accounts, &[], &[], juicy_bets::sweep_unclaimed_funds)
+    };
+
+    set_clock(bet_state.claim_deadline as i64);
+    assert_eq!(
+        error_code(sweep(&mut fixture, &mut authority, &mut system_program)),
+        u32::from(ErrorCode::ClaimWindowStillOpen)
+    );
+
+    set_clock(bet_state.claim_deadline as i64 + 1);
+    sweep(&mut fixture, &mut authority, &mut system_program).unwrap();
+
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.status, BetStateStatus::Settled);
+    assert_eq!(bet_state.running_total_pool, 0);
+    assert_eq!(bet_state.unclaimed_swept, 3_000);
+    assert_eq!(fixture.bet_vault.vault_balance(), 0);
+    assert_eq!(fixture.treasury.lamports, treasury_lamports + 3_000);
+    assert_eq!(fixture.treasury.read::<Treasury>().total_swept, 3_000);
+
+    // The winner missed the deadline
+    assert_eq!(error_code(claim_winnings(&mut fixture, Swap::None)), u32::from(ErrorCode::ClaimWindowExpired));
+}
+
+// Both wagers are still open after the sweep, the bet can only be closed once they are settled as losses and
+// no market vault is left behind
+#[test]
+fn settled_bet_closes_once_every_position_is_resolved_and_its_market_vault_is_closed() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+    let bet_state = fixture.bet_state.read::<BetState>();
+    let mut creator = TestAccount::wallet(bet_state.creator);
+    let mut system_program = TestAccount::system_program();
+
+    set_clock(bet_state.claim_deadline as i64 + 1);
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        fixture.treasury.info(),
+        creator.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+    run_instruction(&accounts, &[], &[], juicy_bets::sweep_unclaimed_funds).unwrap();
+
+    let settled_at = fixture.bet_state.read::<BetState>().settled_at;
+    set_clock(settled_at + SETTLED_BET_RETENTION_SECONDS);
+
+    // Lamport markets never open a market vault, lamports sent to its address do not keep the bet open
+    let mut market_vault = TestAccount::lamport_vault(b"vault", &fixture.bet_state.key);
+
+    let close = |fixture: &mut WagerFixture,
+                 market_vault: &mut TestAccount,
+                 creator: &mut TestAccount,
+                 system_program: &mut TestAccount| {
+        let accounts = [
+            fixture.bet_state.info(),
+            fixture.bet_vault.info(),
+            market_vault.info(),
+            creator.info(),
+            system_program.info(),
+        ];
+        run_instruction(&accounts, &[], &[], juicy_bets::close_settled_bet_state)
+    };
+
+    assert_eq!(
+        error_code(close(&mut fixture, &mut market_vault, &mut creator, &mut system_program)),
+        u32::from(ErrorCode::PositionsStillOpen)
+    );
+
+    acknowledge_loss(&mut fixture).unwrap();
+
+    // The winner's payout went to the treasury, so their wager is settled as a loss as well
+    fixture.bettor.is_signer = false;
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.wager_detail.info(),
+        fixture.user_account.info(),
+        fixture.bettor.info(),
+        fixture.config.info(),
+    ];
+    run_instruction(&accounts, &[], &[], juicy_bets::acknowledge_loss).unwrap();
+
+    let user_account = fixture.user_account.read::<UserAccount>();
+    assert_eq!(user_account.losses, 1);
+    assert!(fixture.wager_detail.is_closed());
+    assert_eq!(fixture.bet_state.read::<BetState>().open_positions, 0);
+
+    let mut open_market_vault = TestAccount::token_account(market_vault.key, &Pubkey::new_unique(), &fixture.bet_state.key, 0);
+    assert_eq!(
+        error_code(close(&mut fixture, &mut open_market_vault, &mut creator, &mut system_program)),
+        u32::from(ErrorCode::MarketVaultStillOpen)
+    );
+
+    close(&mut fixture, &mut market_vault, &mut creator, &mut system_program).unwrap();
+    assert!(fixture.bet_state.is_closed());
+}
+
+#[test]
+fn token_claim_pays_out_of_the_market_vault() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+    let mint = Pubkey::new_unique();
+
+    let mut bet_state = fixture.bet_state.read::<BetState>();
+    bet_state.collateral_mint = mint;
+    fixture.bet_state.write(&bet_state);
+
+    let (market_vault, _) = Pubkey::find_program_address(&[b"vault", fixture.bet_state.key.as_ref()], &crate::ID);
+    let (user_vault, _) = Pubkey::find_program_address(
+        &[b"user_vault", fixture.user_account.key.as_ref(), mint.as_ref()],
+        &crate::ID,
+    );
+    let mut market_vault = TestAccount::token_account(market_vault, &mint, &fixture.bet_state.key, 3_000);
+    let mut user_vault = TestAccount::token_account(user_vault, &mint, &fixture.user_account.key, 500);
+    let mut token_program = TestAccount::token_program();
+
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.wager_detail.info(),
+        fixture.user_account.info(),
+        user_vault.info(),
+        market_vault.info(),
+        fixture.bettor.info(),
+        fixture.config.info(),
+        token_program.info(),
+    ];
+    run_instruction(&accounts, &[], &[], juicy_bets::claim_token_winnings).unwrap();
+
+    assert_eq!(token_amount(&market_vault.data), 0);
+    assert_eq!(token_amount(&user_vault.data), 3_500);
+    assert_eq!(fixture.bet_state.read::<BetState>().running_total_pool, 0);
+    assert!(fixture.wager_detail.is_closed());
+}
+
+#[test]
+fn refund_crank_returns_every_wager_and_closes_the_bet() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Cancelled, BetOutcomeStatus::Void);
+    let mut creator = TestAccount::wallet(fixture.bet_state.read::<BetState>().creator);
+    let mut other_bettor = TestAccount::wallet(fixture.other_bettor_wager_detail.read::<WagerDetail>().bettor);
+    let creator_lamports = creator.lamports + fixture.bet_state.lamports + fixture.bet_vault.lamports - 3_000;
+    let mut system_program = TestAccount::system_program();
+
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        creator.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+    let remaining_accounts = [
+        fixture.wager_detail.info(),
+        fixture.user_account.info(),
+        fixture.account_vault.info(),
+        fixture.bettor.info(),
+        fixture.other_bettor_wager_detail.info(),
+        fixture.other_user_account.info(),
+        fixture.other_account_vault.info(),
+        other_bettor.info(),
+    ];
+    run_instruction(&accounts, &remaining_accounts, &[], juicy_bets::refund_wagers).unwrap();
+
+    assert_eq!(fixture.user_account.read::<UserAccount>().current_balance, 11_000);
+    assert_eq!(fixture.account_vault.vault_balance(), 11_000);
+    assert_eq!(fixture.other_user_account.read::<UserAccount>().current_balance, 12_000);
+    assert_eq!(fixture.other_account_vault.vault_balance(), 12_000);
+    assert!(fixture.other_user_account.read::<UserAccount>().active_wagers.is_empty());
+    assert!(fixture.wager_detail.is_closed());
+    assert!(fixture.other_bettor_wager_detail.is_closed());
+    assert!(fixture.bet_state.is_closed());
+    assert!(fixture.bet_vault.is_closed());
+    assert_eq!(creator.lamports, creator_lamports);
+}
+
+// Tokens sent to the market vault outside of a wager go to the treasury's vault when the crank closes it
+#[test]
+fn token_refund_crank_returns_every_wager_and_closes_the_market() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Cancelled, BetOutcomeStatus::Void);
+    let mint = Pubkey::new_unique();
+
+    let mut bet_state = fixture.bet_state.read::<BetState>();
+    bet_state.collateral_mint = mint;
+    fixture.bet_state.write(&bet_state);
+    // Token markets keep only the outcome bonds in their lamport vault
+    fixture.bet_vault.lamports = Rent::default().minimum_balance(0);
+
+    let token_vault = |prefix: &[u8], account: &Pubkey, amount: u64| {
+        let (key, _) = Pubkey::find_program_address(&[prefix, account.as_ref(), mint.as_ref()], &crate::ID);
+        TestAccount::token_account(key, &mint, account, amount)
+    };
+    let (market_vault, _) = Pubkey::find_program_address(&[b"vault", fixture.bet_state.key.as_ref()], &crate::ID);
+    let mut market_vault = TestAccount::token_account(market_vault, &mint, &fixture.bet_state.key, 3_005);
+    let mut treasury_vault = TestAccount::token_account(
+        Pubkey::find_program_address(&[b"treasury_vault", mint.as_ref()], &crate::ID).0,
+        &mint,
+        &fixture.treasury.key,
+        0,
+    );
+    let mut user_vault = token_vault(b"user_vault", &fixture.user_account.key, 0);
+    let mut other_user_vault = token_vault(b"user_vault", &fixture.other_user_account.key, 0);
+
+    let mut creator = TestAccount::wallet(bet_state.creator);
+    let mut other_bettor = TestAccount::wallet(fixture.other_bettor_wager_detail.read::<WagerDetail>().bettor);
+    let creator_lamports = creator.lamports + fixture.bet_state.lamports + fixture.bet_vault.lamports + market_vault.lamports;
+    let mut token_program = TestAccount::token_program();
+    let mut system_program = TestAccount::system_program();
+
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        market_vault.info(),
+        treasury_vault.info(),
+        creator.info(),
+        fixture.config.info(),
+        token_program.info(),
+        system_program.info(),
+    ];
+    let remaining_accounts = [
+        fixture.wager_detail.info(),
+        fixture.user_account.info(),
+        user_vault.info(),
+        fixture.bettor.info(),
+        fixture.other_bettor_wager_detail.info(),
+        fixture.other_user_account.info(),
+        other_user_vault.info(),
+        other_bettor.info(),
+    ];
+    run_instruction(&accounts, &remaining_accounts, &[], juicy_bets::refund_token_wagers).unwrap();
+
+    assert_eq!(token_amount(&user_vault.data), 1_000);
+    assert_eq!(token_amount(&other_user_vault.data), 2_000);
+    assert_eq!(token_amount(&treasury_vault.data), 5);
+    assert!(fixture.other_user_account.read::<UserAccount>().active_wagers.is_empty());
+    assert!(fixture.wager_detail.is_closed());
+    assert!(fixture.other_bettor_wager_detail.is_closed());
+    assert!(market_vault.is_closed());
+    assert!(fixture.bet_state.is_closed());
+    assert!(fixture.bet_vault.is_closed());
+    assert_eq!(creator.lamports, creator_lamports);
+}
+
+#[test]
+fn creator_can_only_cancel_before_the_end_time() {
+    let cancel = |fixture: &mut WagerFixture| {
+        let mut creator = TestAccount::wallet(fixture.bet_state.read::<BetState>().creator);
+        let accounts = [fixture.bet_state.info(), creator.info(), fixture.config.info()];
+        run_instruction(&accounts, &[], &[], juicy_bets::cancel_bet_state)
+    };
+
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    let end_time = fixture.bet_state.read::<BetState>().end_time as i64;
+
+    set_clock(end_time);
+    assert_eq!(error_code(cancel(&mut fixture)), u32::from(ErrorCode::BettingWindowEnded));
+
+    set_clock(end_time - 1);
+    cancel(&mut fixture).unwrap();
+
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.status, BetStateStatus::Cancelled);
+    assert_eq!(bet_state.bet_outcome, BetOutcomeStatus::Void);
+}
+
+// The creator never closes the bet, resolving it closes it once the end time has passed. With one side empty
+// the bet is voided without reading the price feed.
+#[test]
+fn resolving_closes_a_bet_left_open_past_its_end_time() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    let mut bet_state = fixture.bet_state.read::<BetState>();
+    bet_state.resolution = OutcomeResolution::Unproposed;
+    bet_state.party_two_pool = 0;
+    fixture.bet_state.write(&bet_state);
+
+    let mut price_feed = TestAccount::wallet(bet_state.price_feed);
+    let accounts = [fixture.bet_state.info(), price_feed.info(), fixture.config.info()];
+
+    set_clock(bet_state.end_time as i64 - 1);
+    let result = run_instruction(&accounts, &[], &[], juicy_bets::resolve_bet_state_outcome);
+    assert_eq!(error_code(result), u32::from(ErrorCode::BetNotEnded));
+
+    set_clock(bet_state.end_time as i64);
+    run_instruction(&accounts, &[], &[], juicy_bets::resolve_bet_state_outcome).unwrap();
+
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.status, BetStateStatus::Closed);
+    assert_eq!(bet_state.bet_outcome, BetOutcomeStatus::Void);
+    assert_eq!(bet_state.resolution, OutcomeResolution::Finalized);
+}
+
+#[test]
+fn bet_admin_cannot_make_the_arbitrator_its_resolver() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    let arbitrator = fixture.bet_state.read::<BetState>().arbitrator;
+    let mut admin = TestAccount::wallet(fixture.bet_state.read::<BetState>().admin);
+    let accounts = [fixture.bet_state.info(), admin.info()];
+
+    let result = run_instruction(&accounts, &[], &[], |ctx: Context<SetBetStateAuthorities>| {
+        let (resolver, admin) = (ctx.accounts.bet_state.arbitrator, ctx.accounts.admin.key());
+        juicy_bets::set_bet_state_authorities(ctx, resolver, admin)
+    });
+    assert_eq!(error_code(result), u32::from(ErrorCode::ArbitratorNotIndependent));
+
+    run_instruction(&accounts, &[], &[], |ctx: Context<SetBetStateAuthorities>| {
+        let admin = ctx.accounts.admin.key();
+        juicy_bets::set_bet_state_authorities(ctx, admin, admin)
+    })
+    .unwrap();
+
+    let bet_state = fixture.bet_state.read::<BetState>();
+    assert_eq!(bet_state.resolver, admin.key);
+    assert_eq!(bet_state.arbitrator, arbitrator);
+}
+
+#[test]
+fn voided_bets_cannot_be_swept() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::Void);
+    let bet_state = fixture.bet_state.read::<BetState>();
+    let mut authority = TestAccount::wallet(bet_state.creator);
+    let mut system_program = TestAccount::system_program();
+
+    // Long after the claim deadline, refunds stay open
+    set_clock(bet_state.claim_deadline as i64 + 1);
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        fixture.treasury.info(),
+        authority.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+    assert_eq!(
+        error_code(run_instruction(&accounts, &[], &[], juicy_bets::sweep_unclaimed_funds)),
+        u32::from(ErrorCode::BetIsVoid)
+    );
+
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.bet_vault.info(),
+        fixture.wager_detail.info(),
+        fixture.user_account.info(),
+        fixture.account_vault.info(),
+        fixture.bettor.info(),
+        fixture.config.info(),
+        system_program.info(),
+    ];
+    run_instruction(&accounts, &[], &[], juicy_bets::refund_wager).unwrap();
+
+    assert_eq!(fixture.user_account.read::<UserAccount>().current_balance, 11_000);
+}
+
+#[test]
+fn claim_winnings_rejects_wager_from_another_market() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+
+    assert_eq!(
+        error_code(claim_winnings(&mut fixture, Swap::OtherMarketWager)),
+        u32::from(ErrorCode::WagerBetStateMismatch)
+    );
+}
+
+#[test]
+fn claim_winnings_rejects_wager_from_another_bettor() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+
+    assert_eq!(
+        error_code(claim_winnings(&mut fixture, Swap::OtherBettorWager)),
+        u32::from(ErrorCode::WagerBettorMismatch)
+    );
+}
+
+#[test]
+fn claim_winnings_rejects_another_bettors_user_account() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+
+    assert_eq!(
+        error_code(claim_winnings(&mut fixture, Swap::OtherUserAccount)),
+        u32::from(ErrorCode::NotAccountOwnerToClaimWinnings)
+    );
+}
+
+#[test]
+fn place_wager_rejects_another_bettors_user_account() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+
+    assert_eq!(
+        error_code(place_wager(&mut fixture, Swap::OtherUserAccount)),
+        u32::from(ErrorCode::NotAccountOwnerToPlaceWager)
+    );
+}
+
+// PlaceWager derives the wager address from the given bet and bettor so it can create it on the first
+// wager, which means a position from another market or bettor never matches that address
+#[test]
+fn place_wager_rejects_foreign_wagers_by_address() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+
+    assert_eq!(
+        error_code(place_wager(&mut fixture, Swap::OtherMarketWager)),
+        u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+    );
+    assert_eq!(
+        error_code(place_wager(&mut fixture, Swap::OtherBettorWager)),
+        u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+    );
+}
+
+// Every instruction that moves a user's funds derives the vault from the user account it was given, so
+// another user's vault never matches
+#[test]
+fn vaults_of_another_user_account_are_rejected() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+
+    assert_eq!(
+        error_code(place_wager(&mut fixture, Swap::OtherAccountVault)),
+        u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+    );
+    assert_eq!(
+        error_code(cancel_wager(&mut fixture, Swap::OtherAccountVault)),
+        u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+    );
+
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+
+    assert_eq!(
+        error_code(claim_winnings(&mut fixture, Swap::OtherAccountVault)),
+        u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+    );
+}
+
+#[test]
+fn place_wager_rejected_while_wagers_are_paused() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    fixture.pause(PauseFlags { wagers: true, ..PauseFlags::default() });
+
+    assert_eq!(error_code(place_wager(&mut fixture, Swap::None)), u32::from(ErrorCode::ProgramPaused));
+    assert_eq!(error_code(cancel_wager(&mut fixture, Swap::None)), u32::from(ErrorCode::ProgramPaused));
+}
+
+#[test]
+fn claim_winnings_rejected_only_while_claims_are_paused() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+    fixture.pause(PauseFlags { wagers: true, withdrawals: true, markets: true, deposits: true, claims: false });
+
+    assert!(claim_winnings(&mut fixture, Swap::None).is_ok());
+
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+    fixture.pause(PauseFlags { claims: true, ..PauseFlags::default() });
+
+    assert_eq!(error_code(claim_winnings(&mut fixture, Swap::None)), u32::from(ErrorCode::ProgramPaused));
+}
+
+// Settles the other bettor's losing wager on party two
+fn acknowledge_loss(fixture: &mut WagerFixture) -> Result<()> {
+    let mut other_bettor = TestAccount::wallet(fixture.other_bettor_wager_detail.read::<WagerDetail>().bettor);
+    other_bettor.is_signer = false;
+    let accounts = [
+        fixture.bet_state.info(),
+        fixture.other_bettor_wager_detail.info(),
+        fixture.other_user_account.info(),
+        other_bettor.info(),
+        fixture.config.info(),
+    ];
+
+    run_instruction(&accounts, &[], &[], juicy_bets::acknowledge_loss)
+}
+
+#[test]
+fn acknowledge_loss_rejected_while_claims_are_paused() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Closed, BetOutcomeStatus::PartyOneWin);
+    fixture.pause(PauseFlags { claims: true, ..PauseFlags::default() });
+
+    assert_eq!(error_code(acknowledge_loss(&mut fixture)), u32::from(ErrorCode::ProgramPaused));
+
+    fixture.pause(PauseFlags::default());
+    acknowledge_loss(&mut fixture).unwrap();
+
+    let other_user_account = fixture.other_user_account.read::<UserAccount>();
+    assert_eq!(other_user_account.losses, 1);
+    assert_eq!(other_user_account.realized_profit, -2_000);
+    assert!(other_user_account.active_wagers.is_empty());
+    assert!(fixture.other_bettor_wager_detail.is_closed());
+    assert_eq!(fixture.bet_state.read::<BetState>().open_positions, 1);
+}
+
+#[test]
+fn withdrawals_stay_allowed_during_a_wager_pause() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    fixture.pause(PauseFlags { wagers: true, ..PauseFlags::default() });
+
+    assert!(withdraw_from_account(&mut fixture).is_ok());
+
+    fixture.pause(PauseFlags { withdrawals: true, ..PauseFlags::default() });
+
+    assert_eq!(error_code(withdraw_from_account(&mut fixture)), u32::from(ErrorCode::ProgramPaused));
+}
+
+// An empty balance isn't enough, the account's open wagers still pay out or refund into it
+#[test]
+fn user_account_only_closes_without_active_wagers() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    let mut user_account = fixture.user_account.read::<UserAccount>();
+    user_account.current_balance = 0;
+    fixture.user_account.write(&user_account);
+    fixture.account_vault.lamports = Rent::default().minimum_balance(0);
+
+    let close = |fixture: &mut WagerFixture| {
+        let mut system_program = TestAccount::system_program();
+        let accounts = [
+            fixture.user_account.info(),
+            fixture.account_vault.info(),
+            fixture.bettor.info(),
+            system_program.info(),
+        ];
+        run_instruction(&accounts, &[], &[], juicy_bets::close_user_account)
+    };
+
+    assert_eq!(error_code(close(&mut fixture)), u32::from(ErrorCode::ActiveWagersRemaining));
+
+    user_account.active_wagers.clear();
+    fixture.user_account.write(&user_account);
+    close(&mut fixture).unwrap();
+
+    assert!(fixture.user_account.is_closed());
+    assert!(fixture.account_vault.is_closed());
+}
+
+// Lamports sent straight to the account's vault only count towards the betting balance once it is synced
+#[test]
+fn sync_balance_picks_up_lamports_sent_to_the_vault() {
+    let mut fixture = WagerFixture::new(BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    fixture.account_vault.lamports += 500;
+
+    let sync = |fixture: &mut WagerFixture| {
+        let accounts = [
+            fixture.user_account.info(),
+            fixture.account_vault.info(),
+            fixture.bettor.info(),
+            fixture.config.info(),
+        ];
+        run_instruction(&accounts, &[], &[], juicy_bets::sync_balance)
+    };
+
+    fixture.pause(PauseFlags { withdrawals: true, ..PauseFlags::default() });
+    assert_eq!(error_code(sync(&mut fixture)), u32::from(ErrorCode::ProgramPaused));
+    assert_eq!(fixture.user_account.read::<UserAccount>().current_balance, 10_000);
+
+    fixture.pause(PauseFlags::default());
+    sync(&mut fixture).unwrap();
+
+    assert_eq!(fixture.user_account.read::<UserAccount>().current_balance, 10_500);
+    assert_eq!(fixture.account_vault.vault_balance(), 10_500);
+}
+
+// A pause of claims after the outcome is final pushes the claim deadline back by its length, a pause before
+// it does not
+#[test]
+fn claims_pauses_extend_the_claim_deadline() {
+    let mut config = config(&Pubkey::new_unique(), PauseFlags::default()).read::<Config>();
+    let mut bet_state = bet_state("Crypto.SOL/USD", BetStateStatus::Closed, BetOutcomeStatus::Undecided).read::<BetState>();
+    let finalized_at = 1_650_000_120;
+    let claim_deadline = finalized_at + bet_state.claim_window as i64;
+
+    bet_state.finalize_outcome(BetOutcomeStatus::PartyOneWin, &config, finalized_at).unwrap();
+    assert_eq!(bet_state.claim_deadline as i64, claim_deadline);
+
+    config.set_pause(PauseFlags { claims: true, ..PauseFlags::default() }, finalized_at + 100).unwrap();
+    assert_eq!(bet_state.claim_deadline_after_pauses(&config, finalized_at + 160).unwrap(), claim_deadline + 60);
+    config.set_pause(PauseFlags::default(), finalized_at + 100 + 60 * 60).unwrap();
+    assert_eq!(config.claims_paused_for, 60 * 60);
+
+    assert!(bet_state.check_claim_window(&config, claim_deadline + 60 * 60).is_ok());
+    assert_eq!(
+        error_code(bet_state.check_claim_window(&config, claim_deadline + 60 * 60 + 1)),
+        u32::from(ErrorCode::ClaimWindowExpired)
+    );
+    assert_eq!(
+        error_code(bet_state.sweep_unclaimed(&config, claim_deadline + 60 * 60)),
+        u32::from(ErrorCode::ClaimWindowStillOpen)
+    );
+
+    // Finalized after the pause ended
+    let mut later_bet_state = bet_state.clone();
+    later_bet_state.finalize_outcome(BetOutcomeStatus::PartyOneWin, &config, finalized_at + 2 * 60 * 60).unwrap();
+    assert_eq!(
+        later_bet_state.claim_deadline_after_pauses(&config, finalized_at + 2 * 60 * 60).unwrap(),
+        later_bet_state.claim_deadline as i64
+    );
+
+    assert_eq!(bet_state.sweep_unclaimed(&config, claim_deadline + 60 * 60 + 1).unwrap(), 3_000);
+}
+
+#[test]
+fn config_requires_claim_and_dispute_windows() {
+    let config = config(&Pubkey::new_unique(), PauseFlags::default());
+    let mut config = config.read::<Config>();
+
+    assert!(config.check_claim_window(60 * 60).is_ok());
+    assert_eq!(error_code(config.check_claim_window(60 * 60 - 1)), u32::from(ErrorCode::ClaimWindowTooShort));
+    assert!(config.check_dispute_window(60 * 60).is_ok());
+    assert_eq!(error_code(config.check_dispute_window(0)), u32::from(ErrorCode::DisputeWindowTooShort));
+
+    assert_eq!(error_code(config.set_window_minimums(0, 60)), u32::from(ErrorCode::InvalidClaimWindow));
+    assert_eq!(error_code(config.set_window_minimums(60, 0)), u32::from(ErrorCode::InvalidDisputeWindow));
+
+    config.set_window_minimums(60, 30).unwrap();
+    assert!(config.check_claim_window(60).is_ok());
+    assert!(config.check_dispute_window(30).is_ok());
+
+    let arbitrator = config.arbitrator;
+    assert!(config.check_arbitrator_independence(&[Pubkey::new_unique(), Pubkey::new_unique()]).is_ok());
+    assert_eq!(
+        error_code(config.check_arbitrator_independence(&[Pubkey::new_unique(), arbitrator])),
+        u32::from(ErrorCode::ArbitratorNotIndependent)
+    );
+}
+
+// Counters that no longer add up fail the instruction instead of wrapping
+#[test]
+fn treasury_balance_rejects_inconsistent_counters() {
+    let mut treasury = treasury().read::<Treasury>();
+    treasury.total_fees_collected = 1_000;
+    treasury.total_swept = 500;
+    treasury.total_withdrawn = 1_200;
+    assert_eq!(treasury.balance().unwrap(), 300);
+
+    treasury.total_withdrawn = 1_501;
+    assert_eq!(error_code(treasury.balance()), u32::from(ErrorCode::MathOverflow));
+
+    treasury.total_fees_collected = u64::MAX;
+    assert_eq!(error_code(treasury.balance()), u32::from(ErrorCode::MathOverflow));
+}
+
+// The vault's rent-exempt minimum is never part of what can be withdrawn
+#[test]
+fn withdrawals_leave_the_vault_rent_exempt() {
+    set_syscall_stubs();
+
+    let mut vault = TestAccount::lamport_vault(b"account_vault", &Pubkey::new_unique());
+    assert_eq!(withdrawable_lamports(&vault.info()).unwrap(), 0);
+
+    vault.lamports += 2_500;
+    assert_eq!(withdrawable_lamports(&vault.info()).unwrap(), 2_500);
+
+    vault.lamports = 0;
+    assert_eq!(withdrawable_lamports(&vault.info()).unwrap(), 0);
+}
+
+// MAX_SIZE is what gets allocated, so it has to cover the largest value each account can serialize to
+#[test]
+fn max_sizes_match_borsh_serialized_max() {
+    let bet_state = bet_state(&"X".repeat(BetState::MAX_SYMBOL_LEN), BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    let bet_state = BetState::try_deserialize(&mut &bet_state.data[..]).unwrap();
+    assert_eq!(bet_state.try_to_vec().unwrap().len(), BetState::MAX_SIZE);
+
+    let wager_detail = wager_detail(&Pubkey::new_unique(), &Pubkey::new_unique(), PartyStatus::PartyTwo, u64::MAX);
+    let wager_detail = WagerDetail::try_deserialize(&mut &wager_detail.data[..]).unwrap();
+    assert_eq!(wager_detail.try_to_vec().unwrap().len(), WagerDetail::MAX_SIZE);
+
+    let user_account = user_account(&Pubkey::new_unique(), Vec::new());
+    let mut user_account = UserAccount::try_deserialize(&mut &user_account.data[..]).unwrap();
+    assert_eq!(user_account.try_to_vec().unwrap().len(), UserAccount::BASE_SIZE);
+    user_account.active_wagers = vec![Pubkey::new_unique(); UserAccount::MAX_ACTIVE_WAGERS];
+    assert_eq!(user_account.try_to_vec().unwrap().len(), UserAccount::space_for(UserAccount::MAX_ACTIVE_WAGERS));
+
+    let treasury = treasury();
+    let treasury = Treasury::try_deserialize(&mut &treasury.data[..]).unwrap();
+    assert_eq!(treasury.try_to_vec().unwrap().len(), Treasury::MAX_SIZE);
+
+    let config = config(&Pubkey::new_unique(), PauseFlags::default());
+    let config = Config::try_deserialize(&mut &config.data[..]).unwrap();
+    assert_eq!(config.try_to_vec().unwrap().len(), Config::MAX_SIZE);
+}
+
+// Account definitions as they were at the baseline, before accounts carried a version byte. #[account] derives
+// the same discriminators from the same names, so these serialize to exactly what v1 accounts hold on chain.
+mod baseline {
+    use anchor_lang::prelude::*;
+
+    #[account]
+    pub struct BetState {
+        pub symbol: String,
+        pub creator: Pubkey,
+        pub running_total_pool: u64,
+        pub static_total_pool: u64,
+        pub party_one_pool: u64,
+        pub party_two_pool: u64,
+        pub start_price: u64,
+        pub bet_outcome: BetOutcomeStatus,
+        pub status: BetStateStatus,
+        pub start_time: u64,
+        pub end_time: u64,
+        pub snapshot_price: u128,
+        pub bet_range: BetRange,
+    }
+
+    #[account]
+    pub struct WagerDetail {
+        pub bettor: Pubkey,
+        pub bet_state: Pubkey,
+        pub party: PartyStatus,
+        pub bet_value: u64,
+    }
+
+    #[account]
+    pub struct UserAccount {
+        pub account_owner: Pubkey,
+        pub wins: u64,
+        pub losses: u64,
+        pub active_wagers: Vec<Pubkey>,
+        pub current_balance: u64,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+    pub enum BetOutcomeStatus {
+        Undecided,
+        PartyOneWin,
+        PartyTwoWin,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+    pub enum PartyStatus {
+        PartyOne,
+        PartyTwo,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+    pub enum BetStateStatus {
+        Open,
+        Closed,
+        Settled,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+    pub enum BetRange {
+        NegativeThreeAndOver,
+        NegativeTwoToThree,
+        NegativeOneToTwo,
+        NegativeOneToZero,
+        ZeroToPositiveOne,
+        PositiveOneToTwo,
+        PositiveTwoToThree,
+        PositiveThreeAndOver
+    }
+
+    // Sizes the baseline allocated each account with
+    pub const BET_STATE_SPACE: usize = 220 + 8;
+    pub const WAGER_DETAIL_SPACE: usize = 105 + 8;
+    pub const USER_ACCOUNT_SPACE: usize = 220 + 8;
+
+    pub fn bet_state(bet_outcome: BetOutcomeStatus, status: BetStateStatus) -> BetState {
+        BetState {
+            symbol: "Crypto.SOL/USD".to_string(),
+            creator: Pubkey::new_unique(),
+            running_total_pool: 2_500,
+            static_total_pool: 3_000,
+            party_one_pool: 1_000,
+            party_two_pool: 2_000,
+            start_price: 0,
+            bet_outcome,
+            status,
+            start_time: 1_650_000_000,
+            end_time: 1_650_000_120,
+            snapshot_price: 725_450,
+            bet_range: BetRange::PositiveOneToTwo,
+        }
+    }
+}
+
+fn baseline_account<T: AccountSerialize>(account: &T, space: usize) -> TestAccount {
+    TestAccount::program_account(Pubkey::new_unique(), account, space)
+}
+
+#[test]
+fn baseline_bet_state_reads_as_v1_and_migrates_to_the_current_layout() {
+    let mut legacy = baseline::bet_state(baseline::BetOutcomeStatus::PartyTwoWin, baseline::BetStateStatus::Closed);
+    legacy.running_total_pool = legacy.static_total_pool;
+    let creator = legacy.creator;
+    let mut account = baseline_account(&legacy, baseline::BET_STATE_SPACE);
+    let legacy_key = account.key;
+    let arbitrator = Pubkey::new_unique();
+    let now = 1_700_000_000;
+
+    let bet_state = read_v1_account::<BetState, BetStateV1>(&account.info(), BetStateV1::ACCOUNT_LEN)
+        .unwrap()
+        .into_current(legacy_key, 253, arbitrator, 7 * 24 * 60 * 60, 60 * 60, 0, now)
+        .unwrap();
+
+    assert_eq!(bet_state.version, BetState::CURRENT_VERSION);
+    assert_eq!(bet_state.symbol, "Crypto.SOL/USD");
+    assert_eq!(bet_state.creator, creator);
+    assert_eq!(bet_state.resolver, creator);
+    assert_eq!(bet_state.arbitrator, arbitrator);
+    assert_eq!(bet_state.running_total_pool, 3_000);
+    assert_eq!(bet_state.party_two_pool, 2_000);
+    assert_eq!(bet_state.total_paid_out, 0);
+    assert_eq!(bet_state.winning_pool_claimed, 0);
+    assert_eq!(bet_state.bet_outcome, BetOutcomeStatus::PartyTwoWin);
+    assert_eq!(bet_state.resolution, OutcomeResolution::Finalized);
+    assert_eq!(bet_state.status, BetStateStatus::Closed);
+    assert_eq!(bet_state.end_time, 1_650_000_120);
+    assert_eq!(bet_state.claim_window, 7 * 24 * 60 * 60);
+    assert_eq!(bet_state.claim_deadline, now as u64 + 7 * 24 * 60 * 60);
+    assert_eq!(bet_state.bet_range, BetRange::PositiveOneToTwo);
+    assert_eq!(bet_state.price_feed, Pubkey::default());
+    assert_eq!(bet_state.bump, 253);
+    assert_eq!(bet_state.migrated_from, legacy_key);
+}
+
+// 500 has already been paid out of the pool, but not which winning stakes it paid
+#[test]
+fn decided_baseline_bet_state_that_paid_out_is_not_migrated() {
+    let legacy = baseline::bet_state(baseline::BetOutcomeStatus::PartyTwoWin, baseline::BetStateStatus::Closed);
+    let mut account = baseline_account(&legacy, baseline::BET_STATE_SPACE);
+
+    let result = read_v1_account::<BetState, BetStateV1>(&account.info(), BetStateV1::ACCOUNT_LEN)
+        .unwrap()
+        .into_current(account.key, 253, Pubkey::new_unique(), 100, 60 * 60, 0, 1_700_000_000);
+
+    assert_eq!(error_code(result), u32::from(ErrorCode::LegacyPayoutsStarted));
+}
+
+#[test]
+fn undecided_baseline_bet_state_waits_for_a_proposal() {
+    let legacy = baseline::bet_state(baseline::BetOutcomeStatus::Undecided, baseline::BetStateStatus::Open);
+    let mut account = baseline_account(&legacy, baseline::BET_STATE_SPACE);
+
+    let bet_state = read_v1_account::<BetState, BetStateV1>(&account.info(), BetStateV1::ACCOUNT_LEN)
+        .unwrap()
+        .into_current(account.key, 253, Pubkey::new_unique(), 100, 60 * 60, 0, 1_700_000_000)
+        .unwrap();
+
+    assert_eq!(bet_state.bet_outcome, BetOutcomeStatus::Undecided);
+    assert_eq!(bet_state.resolution, OutcomeResolution::Unproposed);
+    assert_eq!(bet_state.status, BetStateStatus::Open);
+    assert_eq!(bet_state.claim_window, 100);
+    assert_eq!(bet_state.claim_deadline, 0);
+}
+
+#[test]
+fn baseline_wager_detail_and_user_account_read_as_v1() {
+    let bettor = Pubkey::new_unique();
+    let legacy_bet_state = Pubkey::new_unique();
+    let legacy_wager = baseline::WagerDetail {
+        bettor,
+        bet_state: legacy_bet_state,
+        party: baseline::PartyStatus::PartyTwo,
+        bet_value: 980,
+    };
+    let mut wager_account = baseline_account(&legacy_wager, baseline::WAGER_DETAIL_SPACE);
+
+    let wager_detail = read_v1_account::<WagerDetail, WagerDetailV1>(&wager_account.info(), WagerDetailV1::ACCOUNT_LEN).unwrap();
+
+    assert_eq!(wager_detail.bettor, bettor);
+    assert_eq!(wager_detail.bet_state, legacy_bet_state);
+    assert_eq!(wager_detail.party, PartyStatus::PartyTwo);
+    assert_eq!(wager_detail.bet_value, 980);
+
+    let active_wagers = vec![wager_account.key];
+    let legacy_user = baseline::UserAccount {
+        account_owner: bettor,
+        wins: 3,
+        losses: 1,
+        active_wagers: active_wagers.clone(),
+        current_balance: 10_000,
+    };
+    let mut user_account = baseline_account(&legacy_user, baseline::USER_ACCOUNT_SPACE);
+
+    let user_account = read_v1_account::<UserAccount, UserAccountV1>(&user_account.info(), UserAccountV1::ACCOUNT_LEN).unwrap();
+
+    assert_eq!(user_account.account_owner, bettor);
+    assert_eq!(user_account.wins, 3);
+    assert_eq!(user_account.losses, 1);
+    assert_eq!(user_account.active_wagers, active_wagers);
+    assert_eq!(user_account.current_balance, 10_000);
+}
+
+// Only an account of the right type with the size the baseline allocated is read as v1
+#[test]
+fn current_and_foreign_accounts_are_not_read_as_v1() {
+    let mut account = user_account(&Pubkey::new_unique(), Vec::new());
+    assert_eq!(
+        error_code(read_v1_account::<UserAccount, UserAccountV1>(&account.info(), UserAccountV1::ACCOUNT_LEN)),
+        u32::from(ErrorCode::InvalidLegacyAccount)
+    );
+
+    let mut account = bet_state("Crypto.SOL/USD", BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    assert_eq!(
+        error_code(read_v1_account::<BetState, BetStateV1>(&account.info(), BetStateV1::ACCOUNT_LEN)),
+        u32::from(ErrorCode::InvalidLegacyAccount)
+    );
+
+    // A baseline bet state and a baseline user account are the same size
+    let legacy = baseline::bet_state(baseline::BetOutcomeStatus::Undecided, baseline::BetStateStatus::Open);
+    let mut account = baseline_account(&legacy, baseline::BET_STATE_SPACE);
+    assert_eq!(
+        error_code(read_v1_account::<UserAccount, UserAccountV1>(&account.info(), UserAccountV1::ACCOUNT_LEN)),
+        u32::from(ErrorCode::InvalidLegacyAccount)
+    );
+}
+
+// Stand-in for an account type whose layout gained a field in version 3, no real account type has an older
+// versioned layout yet
+#[account]
+struct LayoutV3 {
+    version: u8,
+    value: u64,
+    added: u64,
+}
+
+#[derive(AnchorDeserialize)]
+struct LayoutV2 {
+    _version: u8,
+    value: u64,
+}
+
+impl VersionedLayout for LayoutV3 {
+    const CURRENT_VERSION: u8 = 3;
+    const V1_ACCOUNT_LEN: usize = 8 + 8;
+
+    fn from_version(version: u8, data: &[u8]) -> Result<Self> {
+        match version {
+            2 => {
+                let v2 = LayoutV2::deserialize(&mut &data[..])?;
+                Ok(LayoutV3 { version: LayoutV3::CURRENT_VERSION, value: v2.value, added: 0 })
+            }
+            _ => Err(error!(ErrorCode::UnsupportedLayoutVersion)),
+        }
+    }
+
+    fn required_len(&self) -> usize {
+        8 + 17
+    }
+}
+
+#[test]
+fn upgrade_layout_dispatches_on_the_version_byte() {
+    set_syscall_stubs();
+
+    let layout_account = |version: u8| {
+        let mut data = LayoutV3::discriminator().to_vec();
+        data.push(version);
+        data.extend_from_slice(&42u64.to_le_bytes());
+        data.resize(8 + 17, 0);
+        TestAccount { key: Pubkey::new_unique(), owner: crate::ID, lamports: 1_000_000_000, data, is_signer: false, executable: false }
+    };
+    let mut payer = TestAccount::wallet(Pubkey::new_unique());
+    let mut system_program = TestAccount::system_program();
+
+    let mut account = layout_account(2);
+    let version = upgrade_layout::<LayoutV3>(&account.info(), &payer.info(), &system_program.info()).unwrap();
+    assert_eq!(version, 2);
+
+    let upgraded = account.read::<LayoutV3>();
+    assert_eq!(upgraded.version, 3);
+    assert_eq!(upgraded.value, 42);
+    assert_eq!(upgraded.added, 0);
+
+    for (version, error) in [
+        (1, ErrorCode::UnsupportedLayoutVersion),
+        (3, ErrorCode::LayoutAlreadyCurrent),
+        (4, ErrorCode::UnsupportedLayoutVersion),
+    ] {
+        let mut account = layout_account(version);
+        let result = upgrade_layout::<LayoutV3>(&account.info(), &payer.info(), &system_program.info());
+        assert_eq!(error_code(result), u32::from(error));
+    }
+
+    let mut account = layout_account(2);
+    account.data.truncate(LayoutV3::V1_ACCOUNT_LEN);
+    let result = upgrade_layout::<LayoutV3>(&account.info(), &payer.info(), &system_program.info());
+    assert_eq!(error_code(result), u32::from(ErrorCode::InvalidLayoutAccount));
+}
+
+#[test]
+fn current_and_foreign_accounts_are_not_upgraded() {
+    let upgrade = |account: &mut TestAccount| {
+        let mut payer = TestAccount::wallet(Pubkey::new_unique());
+        let mut system_program = TestAccount::system_program();
+        let accounts = [account.info(), payer.info(), system_program.info()];
+        run_instruction(&accounts, &[], &[], juicy_bets::upgrade_account_layout)
+    };
+
+    let mut account = bet_state("Crypto.SOL/USD", BetStateStatus::Open, BetOutcomeStatus::Undecided);
+    assert_eq!(error_code(upgrade(&mut account)), u32::from(ErrorCode::LayoutAlreadyCurrent));
+
+    let mut account = user_account(&Pubkey::new_unique(), Vec::new());
+    assert_eq!(error_code(upgrade(&mut account)), u32::from(ErrorCode::LayoutAlreadyCurrent));
+
+    let legacy = baseline::bet_state(baseline::BetOutcomeStatus::Undecided, baseline::BetStateStatus::Open);
+    let mut account = baseline_account(&legacy, baseline::BET_STATE_SPACE);
+    assert_eq!(error_code(upgrade(&mut account)), u32::from(ErrorCode::InvalidLayoutAccount));
+
+    let mut account = config(&Pubkey::new_unique(), PauseFlags::default());
+    assert_eq!(error_code(upgrade(&mut account)), u32::from(ErrorCode::InvalidLayoutAccount));
+}
+
+// Runs migrate_user_account against a new account as the init constraint leaves it, allocated and still zeroed,
+// and returns it along with its vault
+fn migrate_user_account(legacy_account: &mut TestAccount, account_owner: &mut TestAccount) -> (TestAccount, TestAccount) {
+    let (key, bump) = Pubkey::find_program_address(&[b"user", account_owner.key.as_ref()], &crate::ID);
+    let space = UserAccount::INITIAL_SIZE + 8;
+    let mut user_account = TestAccount {
+        key,
+        owner: crate::ID,
+        lamports: Rent::default().minimum_balance(space),
+        data: vec![0; space],
+        is_signer: false,
+        executable: false,
+    };
+    let mut account_vault = TestAccount::lamport_vault(b"account_vault", &key);
+    let mut config = config(&Pubkey::new_unique(), PauseFlags::default());
+    let mut system_program = TestAccount::system_program();
+
+    {
+        let user_account_info = user_account.info();
+        let account_vault_info = account_vault.info();
+        let account_owner_info = account_owner.info();
+        let config_info = config.info();
+        let mut accounts = MigrateUserAccount {
+            legacy_user_account: legacy_account.info(),
+            user_account: Account::try_from_unchecked(&user_account_info).unwrap(),
+            account_vault: SystemAccount::try_from(&account_vault_info).unwrap(),
+            account_owner: Signer::try_from(&account_owner_info).unwrap(),
+            config: Account::try_from(&config_info).unwrap(),
+            system_program: system_program.info(),
+        };
+        let mut bumps = BTreeMap::new();
+        bumps.insert("user_account".to_string(), bump);
+
+        juicy_bets::migrate_user_account(Context::new(&crate::ID, &mut accounts, &[], bumps)).unwrap();
+        accounts.exit(&crate::ID).unwrap();
+    }
+
+    (user_account, account_vault)
+}
+
+// A v1 user account's wagers are keypair accounts, each one is tracked again only when it is migrated into its
+// position, so the migrated account starts without any
+#[test]
+fn migrated_user_account_keeps_its_balance_and_drops_legacy_wagers() {
+    set_syscall_stubs();
+
+    let mut account_owner = TestAccount::wallet(Pubkey::new_unique());
+    let legacy = baseline::UserAccount {
+        account_owner: account_owner.key,
+        wins: 3,
+        losses: 1,
+        active_wagers: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        current_balance: 10_000,
+    };
+    let mut legacy_account = baseline_account(&legacy, baseline::USER_ACCOUNT_SPACE);
+    let rent_refund = legacy_account.lamports - 10_000;
+    let owner_lamports = account_owner.lamports;
+
+    let (user_account, account_vault) = migrate_user_account(&mut legacy_account, &mut account_owner);
+
+    let migrated = user_account.read::<UserAccount>();
+    assert_eq!(migrated.account_owner, account_owner.key);
+    assert_eq!(migrated.wins, 3);
+    assert_eq!(migrated.losses, 1);
+    assert!(migrated.active_wagers.is_empty());
+    assert_eq!(migrated.current_balance, 10_000);
+    assert_eq!(account_vault.vault_balance(), 10_000);
+    assert_eq!(account_owner.lamports, owner_lamports + rent_refund);
+    assert!(legacy_account.is_closed());
+}
+
+// A v1 balance the legacy account's lamports don't back is only credited up to what they do, the rent is
+// still refunded
+#[test]
+fn migrated_user_account_only_keeps_the_backed_part_of_its_balance() {
+    set_syscall_stubs();
+
+    let mut account_owner = TestAccount::wallet(Pubkey::new_unique());
+    let legacy = baseline::UserAccount {
+        account_owner: account_owner.key,
+        wins: 0,
+        losses: 0,
+        active_wagers: Vec::new(),
+        current_balance: 10_000,
+    };
+    let mut legacy_account = baseline_account(&legacy, baseline::USER_ACCOUNT_SPACE);
+    let rent = Rent::default().minimum_balance(baseline::USER_ACCOUNT_SPACE);
+    legacy_account.lamports = rent + 4_000;
+    let owner_lamports = account_owner.lamports;
+
+    let (user_account, account_vault) = migrate_user_account(&mut legacy_account, &mut account_owner);
+
+    assert_eq!(user_account.read::<UserAccount>().current_balance, 4_000);
+    assert_eq!(account_vault.vault_balance(), 4_000);
+    assert_eq!(account_owner.lamports, owner_lamports + rent);
+    assert!(legacy_account.is_closed());
+}
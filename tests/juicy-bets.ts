@@ -1,6 +1,7 @@
 import * as anchor from '@project-serum/anchor';
 import { Program } from '@project-serum/anchor';
-import { LAMPORTS_PER_SOL, SystemProgram } from "@solana/web3.js";
+import { LAMPORTS_PER_SOL, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { JuicyBets } from '../target/types/juicy_bets';
 import assert from 'assert';
 import { 
@@ -25,6 +26,173 @@ describe('juicy-bets', () => {
 
   const TICKERS = ["TSLA/USD", "SPY/USD", "AAPL/USD"];
 
+  // Devnet Pyth SOL/USD price account, cloned into the local validator (see Anchor.toml) because creating a
+  // market reads its exponent. Its price is only read by resolveBetStateOutcome, which these tests don't call.
+  const PRICE_FEED = new anchor.web3.PublicKey("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix");
+
+  let treasuryPDA: anchor.web3.PublicKey;
+  let configPDA: anchor.web3.PublicKey;
+
+  // Winners have a week after a bet ends to claim before unclaimed funds can be swept
+  const CLAIM_WINDOW = new anchor.BN(7 * 24 * 60 * 60);
+
+  // Shortest claim window the config allows, kept short so the sweep test can wait it out
+  const MIN_CLAIM_WINDOW = new anchor.BN(1);
+
+  const sleepUntil = async (unixTimestamp: number) => {
+    const millisecondsLeft = (unixTimestamp + 1) * 1000 - Date.now();
+    if (millisecondsLeft > 0) {
+      await new Promise(resolve => setTimeout(resolve, millisecondsLeft));
+    }
+  };
+
+  // The program's ProgramData account records its upgrade authority, the only signer allowed to create the
+  // treasury and the config.
+  // The test validator's genesis programs are not upgradeable, so run these tests against a validator started
+  // with the price feed clone from Anchor.toml, using `anchor test --skip-local-validator`, which deploys with
+  // the provider wallet as upgrade authority.
+  const programDataAddress = async () => (
+    await anchor.web3.PublicKey.findProgramAddress(
+      [program.programId.toBuffer()],
+      new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    )
+  )[0];
+
+  // Bet states and user accounts keep their funds in a system-owned vault PDA next to them
+  const betVaultFor = async (betStatePDA: anchor.web3.PublicKey) => (
+    await anchor.web3.PublicKey.findProgramAddress([Buffer.from("bet_vault"), betStatePDA.toBuffer()], program.programId)
+  )[0];
+
+  const accountVaultFor = async (userAccountPDA: anchor.web3.PublicKey) => (
+    await anchor.web3.PublicKey.findProgramAddress([Buffer.from("account_vault"), userAccountPDA.toBuffer()], program.programId)
+  )[0];
+
+  // Decodes the Anchor events a confirmed transaction logged
+  const eventsEmittedBy = async (signature: string) => {
+    await program.provider.connection.confirmTransaction(signature, "confirmed");
+    const transaction = await program.provider.connection.getTransaction(signature, { commitment: "confirmed" });
+    return transaction.meta.logMessages
+      .filter(log => log.startsWith("Program log: "))
+      .map(log => program.coder.events.decode(log.slice("Program log: ".length)))
+      .filter(event => event !== null);
+  };
+
+  // Settles disputed outcome proposals
+  const ARBITRATOR = anchor.web3.Keypair.generate();
+
+  // Seconds a proposed outcome can be disputed for and the config's minimum, kept short so the tests can wait it out
+  const DISPUTE_WINDOW = new anchor.BN(2);
+
+  // The provider wallet resolves every test bet: propose an outcome, let the dispute window pass and finalize it
+  const proposeAndFinalizeOutcome = async (betStatePDA: anchor.web3.PublicKey, outcome: number) => {
+    await program.rpc.proposeBetStateOutcome(
+      outcome,
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          resolver: providerWallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId
+        }
+      }
+    )
+
+    const betStateAfterProposal = await program.account.betState.fetch(betStatePDA);
+    await sleepUntil(betStateAfterProposal.proposedAt.add(betStateAfterProposal.disputeWindow).toNumber());
+
+    await program.rpc.finalizeBetStateOutcome({
+      accounts: {
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        proposer: providerWallet.publicKey,
+        config: configPDA,
+        systemProgram: SystemProgram.programId
+      }
+    })
+  };
+
+
+  // Treasury Tests //
+
+  it('Initialize the treasury that collects the take fee', async () => {
+
+    [treasuryPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+
+    await program.rpc.initializeTreasury({
+      accounts: {
+        treasury: treasuryPDA,
+        authority: providerWallet.publicKey,
+        programData: await programDataAddress(),
+        systemProgram: anchor.web3.SystemProgram.programId
+      }
+    });
+
+    const treasuryAccount = await program.account.treasury.fetch(treasuryPDA);
+
+    assert.ok(treasuryAccount.authority.equals(providerWallet.publicKey));
+    assert.ok(treasuryAccount.totalFeesCollected.eq(new anchor.BN(0)));
+    assert.ok(treasuryAccount.totalWithdrawn.eq(new anchor.BN(0)));
+
+  });
+
+
+  // Config Tests //
+
+  it('Initialize the program config that sets the take fee and wager limits', async () => {
+
+    [configPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("config")],
+      program.programId
+    );
+
+    await program.rpc.initializeConfig(ARBITRATOR.publicKey, 200, new anchor.BN(1000), new anchor.BN(100 * LAMPORTS_PER_SOL), MIN_CLAIM_WINDOW, DISPUTE_WINDOW, {
+      accounts: {
+        config: configPDA,
+        treasury: treasuryPDA,
+        admin: providerWallet.publicKey,
+        programData: await programDataAddress(),
+        systemProgram: anchor.web3.SystemProgram.programId
+      }
+    });
+
+    const configAccount = await program.account.config.fetch(configPDA);
+
+    assert.ok(configAccount.admin.equals(providerWallet.publicKey));
+    assert.ok(configAccount.treasury.equals(treasuryPDA));
+    assert.ok(configAccount.arbitrator.equals(ARBITRATOR.publicKey));
+    assert.equal(configAccount.feeBps, 200);
+    assert.ok(configAccount.minWager.eq(new anchor.BN(1000)));
+    assert.deepEqual(configAccount.pause, { markets: false, wagers: false, claims: false, withdrawals: false, deposits: false });
+
+  });
+
+  it('Config admin can pause wagers on their own and unpause them again', async () => {
+
+    await program.rpc.setPauseFlags({ markets: false, wagers: true, claims: false, withdrawals: false, deposits: false }, {
+      accounts: {
+        config: configPDA,
+        admin: providerWallet.publicKey
+      }
+    });
+
+    const configWhilePaused = await program.account.config.fetch(configPDA);
+    assert.deepEqual(configWhilePaused.pause, { markets: false, wagers: true, claims: false, withdrawals: false, deposits: false });
+
+    await program.rpc.setPauseFlags({ markets: false, wagers: false, claims: false, withdrawals: false, deposits: false }, {
+      accounts: {
+        config: configPDA,
+        admin: providerWallet.publicKey
+      }
+    });
+
+    const configAfterUnpause = await program.account.config.fetch(configPDA);
+    assert.equal(configAfterUnpause.pause.wagers, false);
+
+  });
+
 
   // E2E Betting Tests //
 
@@ -66,6 +234,8 @@ describe('juicy-bets', () => {
     assert.ok(betStateAccount.partyTwoPool.eq(new anchor.BN(0)));
     assert.ok(betStateAccount.status.hasOwnProperty("open"));
     assert.ok(betStateAccount.betOutcome.hasOwnProperty("undecided"));
+    assert.ok(betStateAccount.resolver.equals(providerWallet.publicKey));
+    assert.ok(betStateAccount.admin.equals(providerWallet.publicKey));
 
 
 
@@ -102,7 +272,6 @@ describe('juicy-bets', () => {
 
     ///// ***** USER DEPOSITS LAMPORTS INTO USER ACCOUNT ***** /////
 
-    const lamports_to_deposit_num = LAMPORTS_PER_SOL * 1
     const lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("Starting the 'deposit into user account' functionality...");
@@ -110,15 +279,10 @@ describe('juicy-bets', () => {
     await program.rpc.depositIntoAccount(lamports_to_deposit, {
       accounts: {
         userAccount: userAccountKP.publicKey,
-        accountOwner: bettorKP.publicKey
+        accountOwner: bettorKP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: bettorKP.publicKey,
-          lamports: lamports_to_deposit_num,
-          toPubkey: userAccountKP.publicKey
-        })
-      ],
       signers:[userAccountKP, bettorKP]
     })
 
@@ -193,6 +357,7 @@ describe('juicy-bets', () => {
           wagerDetail: wagerDetailKP.publicKey,
           userAccount: userAccountKP.publicKey,
           bettor: bettorKP.publicKey,
+          config: configPDA
         },
         signers: [bettorKP]
       });
@@ -240,40 +405,55 @@ describe('juicy-bets', () => {
 
      ///// ***** INITIALIZE BET FUNCTIONALITY ***** /////
 
-    // Generate a new random keypair for betState
-    const betStateKP = anchor.web3.Keypair.generate();
     const tradingPair = TICKERS[0];
 
-    const start = new anchor.BN(Date.now());
-    const duration = new anchor.BN(5 * 60 * 1000);
+    // Start and end times are unix timestamps in seconds, checked against the cluster clock
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+    const duration = new anchor.BN(2 * 60);
 
     console.log("Starting the 'initialize bet state' functionality...");
     console.log('--------------------')
     console.log(`Bet Creator Sol Balance pre bet init: ${await program.provider.connection.getBalance(betCreatorKeyPair.publicKey)/LAMPORTS_PER_SOL}`)
 
+    const symbol = 'Equity.US.SPY/USD';
+    const betRange = 0;
+
+    // The bet state address is derived from its symbol, start time and range
+    const [betStatePDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("bet"), Buffer.from(symbol), start.toArrayLike(Buffer, "le", 8), Buffer.from([betRange])],
+      program.programId
+    );
+
     await program.rpc.initializeBetState(
       start,
       duration,
-      'Equity.US.SPY/USD',
+      symbol,
       new anchor.BN(725.45 * 1000),
-      0,
+      betRange,
+      providerWallet.publicKey,
+      providerWallet.publicKey,
+      CLAIM_WINDOW,
+      DISPUTE_WINDOW,
       {
         accounts: {
-          betState: betStateKP.publicKey,
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
           betCreator: providerWallet.publicKey,
+          priceFeed: PRICE_FEED,
+          config: configPDA,
           systemProgram: anchor.web3.SystemProgram.programId
-        },
-        signers:[betStateKP]
+        }
       },
     )
 
-    let betStateAccount = await program.account.betState.fetch(betStateKP.publicKey);
+    let betStateAccount = await program.account.betState.fetch(betStatePDA);
 
     await program.account.betState.all
 
     console.log(`${JSON.stringify(betStateAccount)}`);
 
     assert.ok(betStateAccount);
+    assert.equal(betStateAccount.version, 4);
     assert.ok(betStateAccount.staticTotalPool.eq(new anchor.BN(0)));
     assert.ok(betStateAccount.runningTotalPool.eq(new anchor.BN(0)));
     assert.ok(betStateAccount.partyOnePool.eq(new anchor.BN(0)));
@@ -297,23 +477,28 @@ describe('juicy-bets', () => {
     const bettor1_airdrop_sig = await program.provider.connection.requestAirdrop(user1KP.publicKey, 2000000000)
     await program.provider.connection.confirmTransaction(bettor1_airdrop_sig, "finalized");
 
-    const user1AccountKP = anchor.web3.Keypair.generate();
+    const [user1AccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user"), user1KP.publicKey.toBuffer()],
+      program.programId
+    );
 
     console.log("Initializaing User 1 Account...");
 
     await program.rpc.initializeUserAccount({
       accounts: {
-        userAccount: user1AccountKP.publicKey,
+        userAccount: user1AccountPDA,
+        accountVault: await accountVaultFor(user1AccountPDA),
         accountOwner: user1KP.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user1KP, user1AccountKP]
+      signers: [user1KP]
     })
 
-    const userAccount = await program.account.userAccount.fetch(user1AccountKP.publicKey);
+    const userAccount = await program.account.userAccount.fetch(user1AccountPDA);
 
     assert.ok(userAccount);
     console.log(`Bettor 1 User Account: ${JSON.stringify(userAccount)}`);
+    assert.equal(userAccount.version, 3)
     assert.equal(userAccount.currentBalance.toNumber(), 0)
     assert.equal(userAccount.wins.toNumber(), 0)
     assert.equal(userAccount.losses.toNumber(), 0)
@@ -323,27 +508,22 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 1 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
 
-    const user1_lamports_to_deposit_num = LAMPORTS_PER_SOL * 1
     const user1_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("User 1 Depositing...");
 
     await program.rpc.depositIntoAccount(user1_lamports_to_deposit, {
       accounts: {
-        userAccount: user1AccountKP.publicKey,
-        accountOwner: user1KP.publicKey
+        userAccount: user1AccountPDA,
+        accountVault: await accountVaultFor(user1AccountPDA),
+        accountOwner: user1KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user1KP.publicKey,
-          lamports: user1_lamports_to_deposit_num,
-          toPubkey: user1AccountKP.publicKey
-        })
-      ],
       signers:[user1KP]
     })
 
-    const user1AccountAfterDeposit = await program.account.userAccount.fetch(user1AccountKP.publicKey);
+    const user1AccountAfterDeposit = await program.account.userAccount.fetch(user1AccountPDA);
 
     console.log(`User 1 User Account After 1 Sol Deposit: ${JSON.stringify(user1AccountAfterDeposit)}`);
     assert.equal(user1AccountAfterDeposit.currentBalance.toNumber(), 1000000000);
@@ -351,45 +531,59 @@ describe('juicy-bets', () => {
     assert.equal(user1AccountAfterDeposit.losses.toNumber(), 0);
     assert.equal(user1AccountAfterDeposit.accountOwner.toString(), user1KP.publicKey.toString());
 
+    // The deposit moves real lamports into the account's vault, which holds its rent-exempt reserve plus the deposit.
+    // The user account itself only holds its own rent.
+    const user1AccountInfo = await program.provider.connection.getAccountInfo(user1AccountPDA);
+    const user1AccountRent = await program.provider.connection.getMinimumBalanceForRentExemption(user1AccountInfo.data.length);
+    const vaultRent = await program.provider.connection.getMinimumBalanceForRentExemption(0);
+    assert.equal(await program.provider.connection.getBalance(user1AccountPDA), user1AccountRent);
+    assert.equal(await program.provider.connection.getBalance(await accountVaultFor(user1AccountPDA)), vaultRent + user1_lamports_to_deposit.toNumber());
+
 
 
 
     ///// ***** USER 1 PLACE WAGER FUNCTIONALITY ***** /////
 
-    const wagerDetail1KP = anchor.web3.Keypair.generate();
     const party = 1;
+    const [wagerDetail1PDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("wager"), betStatePDA.toBuffer(), user1KP.publicKey.toBuffer(), Buffer.from([party])],
+      program.programId
+    );
     const user1_lamports_to_wager = new anchor.BN((LAMPORTS_PER_SOL * 0.5) * JUICED_BETS_TAKE_RATE);
 
-    console.log(`Bet State lamports before first wager placement: ${await program.provider.connection.getBalance(betStateKP.publicKey)/LAMPORTS_PER_SOL}`)
-    console.log(`User Account lamports before first wager placement: ${await program.provider.connection.getBalance(user1AccountKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`Bet vault lamports before first wager placement: ${await program.provider.connection.getBalance(await betVaultFor(betStatePDA))/LAMPORTS_PER_SOL}`)
+    console.log(`User vault lamports before first wager placement: ${await program.provider.connection.getBalance(await accountVaultFor(user1AccountPDA))/LAMPORTS_PER_SOL}`)
     
     console.log("User 1 placing wager...");
 
     await program.rpc.placeWager(party, user1_lamports_to_wager, {
       accounts: {
-        betState: betStateKP.publicKey,
-        wagerDetail: wagerDetail1KP.publicKey,
-        userAccount: user1AccountKP.publicKey,
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        wagerDetail: wagerDetail1PDA,
+        userAccount: user1AccountPDA,
+        accountVault: await accountVaultFor(user1AccountPDA),
         bettorAccount: user1KP.publicKey,
-        betCreator: betCreatorKeyPair.publicKey,
+        config: configPDA,
+        treasury: treasuryPDA,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user1KP, wagerDetail1KP]
+      signers: [user1KP]
     })
 
-    const wagerDetails1Account = await program.account.wagerDetail.fetch(wagerDetail1KP.publicKey);
-    const betStateAfterWager = await program.account.betState.fetch(betStateKP.publicKey);
-    const user1AccountAfterWager = await program.account.userAccount.fetch(user1AccountKP.publicKey);
+    const wagerDetails1Account = await program.account.wagerDetail.fetch(wagerDetail1PDA);
+    const betStateAfterWager = await program.account.betState.fetch(betStatePDA);
+    const user1AccountAfterWager = await program.account.userAccount.fetch(user1AccountPDA);
 
     // Test the newly created bettor detail account and associated changes
     assert.ok(wagerDetails1Account);
     console.log(wagerDetails1Account)
     assert.ok(wagerDetails1Account.bettor.equals(user1KP.publicKey), `The attached bettor address: ${wagerDetails1Account.bettor.toString()} attached to this bettor detail does not match with the correct betting user address: ${user1KP.publicKey.toString()}`);
-    assert.ok(wagerDetails1Account.betState.equals(betStateKP.publicKey), `The attached bet state: ${wagerDetails1Account.betState.toString()} attached to this bettor detail does not match with the correct bet state: ${betStateKP.publicKey.toString()}`);
+    assert.ok(wagerDetails1Account.betState.equals(betStatePDA), `The attached bet state: ${wagerDetails1Account.betState.toString()} attached to this bettor detail does not match with the correct bet state: ${betStatePDA.toString()}`);
     assert.ok(wagerDetails1Account.betValue.eq(new anchor.BN(user1_lamports_to_wager.toNumber()/JUICED_BETS_TAKE_RATE)), `Lamports we expect to bet: ${user1_lamports_to_wager} are not equal to the expected amount: ${wagerDetails1Account.betValue.toNumber()}`);
 
     // Test the right number of lamports were transferred from user account to betState
-    console.log(`User 1 Account lamports after first wager placement: ${await program.provider.connection.getBalance(user1AccountKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`User 1 vault lamports after first wager placement: ${await program.provider.connection.getBalance(await accountVaultFor(user1AccountPDA))/LAMPORTS_PER_SOL}`)
     console.log(`Bettor 1 User Account Balance after wager: ${user1AccountAfterWager.currentBalance.toNumber()/LAMPORTS_PER_SOL}`);
     console.log('--------------------')
     console.log(`Bet Creator Sol Balance after Bettor 1 places wager: ${await program.provider.connection.getBalance(betCreatorKeyPair.publicKey)/LAMPORTS_PER_SOL}`)
@@ -400,7 +594,7 @@ describe('juicy-bets', () => {
     console.log(`Bet state running total pool after first wager placement: ${betStateAfterWager.runningTotalPool.toNumber()/LAMPORTS_PER_SOL}`);
     console.log(`Bet state party 1 pool after first wager placement: ${betStateAfterWager.partyOnePool.toNumber()/LAMPORTS_PER_SOL}`);
     console.log(`Bet state party 2 pool after first wager placement: ${betStateAfterWager.partyTwoPool.toNumber()/LAMPORTS_PER_SOL}`);
-    console.log(`Bet State lamports after first wager placement: ${await program.provider.connection.getBalance(betStateKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`Bet vault lamports after first wager placement: ${await program.provider.connection.getBalance(await betVaultFor(betStatePDA))/LAMPORTS_PER_SOL}`)
 
 
 
@@ -417,20 +611,24 @@ describe('juicy-bets', () => {
     const user2_airdrop_sig = await program.provider.connection.requestAirdrop(user2KP.publicKey, 2000000000)
     await program.provider.connection.confirmTransaction(user2_airdrop_sig, "finalized");
 
-    const user2AccountKP = anchor.web3.Keypair.generate();
+    const [user2AccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user"), user2KP.publicKey.toBuffer()],
+      program.programId
+    );
 
     console.log("Initializaing User 2 Account...");
 
     await program.rpc.initializeUserAccount({
       accounts: {
-        userAccount: user2AccountKP.publicKey,
+        userAccount: user2AccountPDA,
+        accountVault: await accountVaultFor(user2AccountPDA),
         accountOwner: user2KP.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user2KP, user2AccountKP]
+      signers: [user2KP]
     })
 
-    const user2Account = await program.account.userAccount.fetch(user2AccountKP.publicKey);
+    const user2Account = await program.account.userAccount.fetch(user2AccountPDA);
 
     assert.ok(user2Account);
     console.log(`Bettor 2 User Account: ${JSON.stringify(user2Account)}`);
@@ -443,27 +641,22 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 2 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
 
-    const user2_lamports_deposit_num = LAMPORTS_PER_SOL * 1
     const user2_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("User 2 Depositing...");
 
     await program.rpc.depositIntoAccount(user2_lamports_to_deposit, {
       accounts: {
-        userAccount: user2AccountKP.publicKey,
-        accountOwner: user2KP.publicKey
+        userAccount: user2AccountPDA,
+        accountVault: await accountVaultFor(user2AccountPDA),
+        accountOwner: user2KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user2KP.publicKey,
-          lamports: user2_lamports_deposit_num,
-          toPubkey: user2AccountKP.publicKey
-        })
-      ],
       signers:[user2KP]
     })
 
-    const user2AccountAfterDeposit = await program.account.userAccount.fetch(user2AccountKP.publicKey);
+    const user2AccountAfterDeposit = await program.account.userAccount.fetch(user2AccountPDA);
 
     console.log(`User 2 User Account After 1 Sol Deposit: ${JSON.stringify(user2AccountAfterDeposit)}`);
     assert.equal(user2AccountAfterDeposit.currentBalance.toNumber(), 1000000000);
@@ -476,39 +669,45 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 2 PLACE WAGER FUNCTIONALITY ***** /////
 
-    const wagerDetail2KP = anchor.web3.Keypair.generate();
     const user2_party = 1;
+    const [wagerDetail2PDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("wager"), betStatePDA.toBuffer(), user2KP.publicKey.toBuffer(), Buffer.from([user2_party])],
+      program.programId
+    );
     const user2_lamports_to_wager = new anchor.BN((LAMPORTS_PER_SOL * 0.5) * JUICED_BETS_TAKE_RATE);
 
-    console.log(`Bet State lamports before second wager placement: ${await program.provider.connection.getBalance(betStateKP.publicKey)/LAMPORTS_PER_SOL}`)
-    console.log(`User 2 Account lamports before second wager placement: ${await program.provider.connection.getBalance(user2AccountKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`Bet vault lamports before second wager placement: ${await program.provider.connection.getBalance(await betVaultFor(betStatePDA))/LAMPORTS_PER_SOL}`)
+    console.log(`User 2 vault lamports before second wager placement: ${await program.provider.connection.getBalance(await accountVaultFor(user2AccountPDA))/LAMPORTS_PER_SOL}`)
     
     console.log("User 2 placing wager...");
 
     await program.rpc.placeWager(user2_party, user2_lamports_to_wager, {
       accounts: {
-        betState: betStateKP.publicKey,
-        wagerDetail: wagerDetail2KP.publicKey,
-        userAccount: user2AccountKP.publicKey,
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        wagerDetail: wagerDetail2PDA,
+        userAccount: user2AccountPDA,
+        accountVault: await accountVaultFor(user2AccountPDA),
         bettorAccount: user2KP.publicKey,
-        betCreator: betCreatorKeyPair.publicKey,
+        config: configPDA,
+        treasury: treasuryPDA,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user2KP, wagerDetail2KP]
+      signers: [user2KP]
     })
 
-    const wagerDetails2Account = await program.account.wagerDetail.fetch(wagerDetail2KP.publicKey);
-    const userAccount2AfterWager = await program.account.userAccount.fetch(user2AccountKP.publicKey);
+    const wagerDetails2Account = await program.account.wagerDetail.fetch(wagerDetail2PDA);
+    const userAccount2AfterWager = await program.account.userAccount.fetch(user2AccountPDA);
 
     // Test the newly created bettor detail account and associated changes
     assert.ok(wagerDetails2Account);
     console.log(wagerDetails2Account)
     assert.ok(wagerDetails2Account.bettor.equals(user2KP.publicKey), `The attached bettor address: ${wagerDetails2Account.bettor.toString()} attached to this bettor detail does not match with the correct betting user address: ${user2KP.publicKey.toString()}`);
-    assert.ok(wagerDetails2Account.betState.equals(betStateKP.publicKey), `The attached bet state: ${wagerDetails2Account.betState.toString()} attached to this bettor detail does not match with the correct bet state: ${betStateKP.publicKey.toString()}`);
+    assert.ok(wagerDetails2Account.betState.equals(betStatePDA), `The attached bet state: ${wagerDetails2Account.betState.toString()} attached to this bettor detail does not match with the correct bet state: ${betStatePDA.toString()}`);
     assert.ok(wagerDetails2Account.betValue.eq(new anchor.BN(user2_lamports_to_wager.toNumber()/JUICED_BETS_TAKE_RATE)), `Lamports we expect to bet: ${user2_lamports_to_wager} are not equal to the expected amount: ${wagerDetails2Account.betValue.toNumber()}`);
 
     // Test the right number of lamports were transferred from user account to betState
-    console.log(`User 2 Account lamports after first wager placement: ${await program.provider.connection.getBalance(user2AccountKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`User 2 vault lamports after first wager placement: ${await program.provider.connection.getBalance(await accountVaultFor(user2AccountPDA))/LAMPORTS_PER_SOL}`)
     console.log(`Bettor 2 User Account Balance after wager: ${userAccount2AfterWager.currentBalance.toNumber()/LAMPORTS_PER_SOL}`);
     console.log('--------------------')
     console.log(`Bet Creator Sol Balance after Bettor 2 places wager: ${await program.provider.connection.getBalance(betCreatorKeyPair.publicKey)/LAMPORTS_PER_SOL}`)
@@ -519,7 +718,7 @@ describe('juicy-bets', () => {
     console.log(`Bet state running total pool after second wager placement: ${betStateAfterWager.runningTotalPool.toNumber()/LAMPORTS_PER_SOL}`);
     console.log(`Bet state party 1 pool after second wager placement: ${betStateAfterWager.partyOnePool.toNumber()/LAMPORTS_PER_SOL}`);
     console.log(`Bet state party 2 pool after second wager placement: ${betStateAfterWager.partyTwoPool.toNumber()/LAMPORTS_PER_SOL}`);
-    console.log(`Bet State lamports after second wager placement: ${await program.provider.connection.getBalance(betStateKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`Bet vault lamports after second wager placement: ${await program.provider.connection.getBalance(await betVaultFor(betStatePDA))/LAMPORTS_PER_SOL}`)
 
 
 
@@ -537,20 +736,24 @@ describe('juicy-bets', () => {
     const user3_airdrop_sig = await program.provider.connection.requestAirdrop(user3KP.publicKey, 2000000000)
     await program.provider.connection.confirmTransaction(user3_airdrop_sig, "finalized");
 
-    const user3AccountKP = anchor.web3.Keypair.generate();
+    const [user3AccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user"), user3KP.publicKey.toBuffer()],
+      program.programId
+    );
 
     console.log("Initializaing User 3 Account...");
 
     await program.rpc.initializeUserAccount({
       accounts: {
-        userAccount: user3AccountKP.publicKey,
+        userAccount: user3AccountPDA,
+        accountVault: await accountVaultFor(user3AccountPDA),
         accountOwner: user3KP.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user3KP, user3AccountKP]
+      signers: [user3KP]
     })
 
-    const user3Account = await program.account.userAccount.fetch(user3AccountKP.publicKey);
+    const user3Account = await program.account.userAccount.fetch(user3AccountPDA);
 
     assert.ok(user3Account);
     console.log(`Bettor 3 User Account: ${JSON.stringify(user3Account)}`);
@@ -563,27 +766,22 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 3 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
 
-    const user3_lamports_deposit_num = LAMPORTS_PER_SOL * 1
     const user3_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("User 3 Depositing...");
 
     await program.rpc.depositIntoAccount(user3_lamports_to_deposit, {
       accounts: {
-        userAccount: user3AccountKP.publicKey,
-        accountOwner: user3KP.publicKey
+        userAccount: user3AccountPDA,
+        accountVault: await accountVaultFor(user3AccountPDA),
+        accountOwner: user3KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user3KP.publicKey,
-          lamports: user3_lamports_deposit_num,
-          toPubkey: user3AccountKP.publicKey
-        })
-      ],
       signers:[user3KP]
     })
 
-    const user3AccountAfterDeposit = await program.account.userAccount.fetch(user3AccountKP.publicKey);
+    const user3AccountAfterDeposit = await program.account.userAccount.fetch(user3AccountPDA);
 
     console.log(`User 3 User Account After 1 Sol Deposit: ${JSON.stringify(user3AccountAfterDeposit)}`);
     assert.equal(user3AccountAfterDeposit.currentBalance.toNumber(), 1000000000);
@@ -596,39 +794,45 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 3 PLACE WAGER FUNCTIONALITY ***** /////
 
-    const wagerDetail3KP = anchor.web3.Keypair.generate();
     const user3_party = 2;
+    const [wagerDetail3PDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("wager"), betStatePDA.toBuffer(), user3KP.publicKey.toBuffer(), Buffer.from([user3_party])],
+      program.programId
+    );
     const user3_lamports_to_wager = new anchor.BN((LAMPORTS_PER_SOL * 0.5) * JUICED_BETS_TAKE_RATE);
 
-    console.log(`Bet State lamports before third wager placement: ${await program.provider.connection.getBalance(betStateKP.publicKey)/LAMPORTS_PER_SOL}`)
-    console.log(`User 3 Account lamports before third wager placement: ${await program.provider.connection.getBalance(user3AccountKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`Bet vault lamports before third wager placement: ${await program.provider.connection.getBalance(await betVaultFor(betStatePDA))/LAMPORTS_PER_SOL}`)
+    console.log(`User 3 vault lamports before third wager placement: ${await program.provider.connection.getBalance(await accountVaultFor(user3AccountPDA))/LAMPORTS_PER_SOL}`)
     
     console.log("User 3 placing wager...");
 
     await program.rpc.placeWager(user3_party, user3_lamports_to_wager, {
       accounts: {
-        betState: betStateKP.publicKey,
-        wagerDetail: wagerDetail3KP.publicKey,
-        userAccount: user3AccountKP.publicKey,
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        wagerDetail: wagerDetail3PDA,
+        userAccount: user3AccountPDA,
+        accountVault: await accountVaultFor(user3AccountPDA),
         bettorAccount: user3KP.publicKey,
-        betCreator: betCreatorKeyPair.publicKey,
+        config: configPDA,
+        treasury: treasuryPDA,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user3KP, wagerDetail3KP]
+      signers: [user3KP]
     })
 
-    const wagerDetails3Account = await program.account.wagerDetail.fetch(wagerDetail3KP.publicKey);
-    const userAccount3AfterWager = await program.account.userAccount.fetch(user3AccountKP.publicKey);
+    const wagerDetails3Account = await program.account.wagerDetail.fetch(wagerDetail3PDA);
+    const userAccount3AfterWager = await program.account.userAccount.fetch(user3AccountPDA);
 
     // Test the newly created bettor detail account and associated changes
     assert.ok(wagerDetails3Account);
     console.log(wagerDetails3Account)
     assert.ok(wagerDetails3Account.bettor.equals(user3KP.publicKey), `The attached bettor address: ${wagerDetails3Account.bettor.toString()} attached to this bettor detail does not match with the correct betting user address: ${user3KP.publicKey.toString()}`);
-    assert.ok(wagerDetails3Account.betState.equals(betStateKP.publicKey), `The attached bet state: ${wagerDetails3Account.betState.toString()} attached to this bettor detail does not match with the correct bet state: ${betStateKP.publicKey.toString()}`);
+    assert.ok(wagerDetails3Account.betState.equals(betStatePDA), `The attached bet state: ${wagerDetails3Account.betState.toString()} attached to this bettor detail does not match with the correct bet state: ${betStatePDA.toString()}`);
     assert.ok(wagerDetails3Account.betValue.eq(new anchor.BN(user3_lamports_to_wager.toNumber()/JUICED_BETS_TAKE_RATE)), `Lamports we expect to bet: ${user3_lamports_to_wager} are not equal to the expected amount: ${wagerDetails3Account.betValue.toNumber()}`);
 
     // Test the right number of lamports were transferred from user account to betState
-    console.log(`User 3 Account lamports after third wager placement: ${await program.provider.connection.getBalance(user3AccountKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`User 3 vault lamports after third wager placement: ${await program.provider.connection.getBalance(await accountVaultFor(user3AccountPDA))/LAMPORTS_PER_SOL}`)
     console.log(`Bettor 3 User Account Balance after wager: ${userAccount3AfterWager.currentBalance.toNumber()/LAMPORTS_PER_SOL}`);
     console.log('--------------------')
     console.log(`Bet Creator Sol Balance after Bettor 3 places wager: ${await program.provider.connection.getBalance(betCreatorKeyPair.publicKey)/LAMPORTS_PER_SOL}`)
@@ -639,29 +843,54 @@ describe('juicy-bets', () => {
     console.log(`Bet state running total pool after third wager placement: ${betStateAfterWager.runningTotalPool.toNumber()/LAMPORTS_PER_SOL}`);
     console.log(`Bet state party 1 pool after third wager placement: ${betStateAfterWager.partyOnePool.toNumber()/LAMPORTS_PER_SOL}`);
     console.log(`Bet state party 2 pool after third wager placement: ${betStateAfterWager.partyTwoPool.toNumber()/LAMPORTS_PER_SOL}`);
-    console.log(`Bet State lamports after third wager placement: ${await program.provider.connection.getBalance(betStateKP.publicKey)/LAMPORTS_PER_SOL}`)
+    console.log(`Bet vault lamports after third wager placement: ${await program.provider.connection.getBalance(await betVaultFor(betStatePDA))/LAMPORTS_PER_SOL}`)
 
 
 
 
-    ///// ***** CLOSING A BET STATE ***** /////
+    ///// ***** TREASURY COLLECTS THE TAKE FEE FOR ALL THREE WAGERS ***** /////
 
-    const end_time = new anchor.BN(Date.now());
+    const treasuryAfterWagers = await program.account.treasury.fetch(treasuryPDA);
+    const expectedFees = user1_lamports_to_wager.sub(wagerDetails1Account.betValue)
+      .add(user2_lamports_to_wager.sub(wagerDetails2Account.betValue))
+      .add(user3_lamports_to_wager.sub(wagerDetails3Account.betValue));
 
-    const betStateBeforeClosing = await program.account.betState.fetch(betStateKP.publicKey);
+    assert.ok(treasuryAfterWagers.totalFeesCollected.eq(expectedFees));
+    assert.equal(user1AccountAfterWager.currentBalance.toNumber(), user1_lamports_to_deposit.sub(user1_lamports_to_wager).toNumber());
+
+
+
+
+    ///// ***** CLOSING A BET STATE ***** /////
+
+    const betStateBeforeClosing = await program.account.betState.fetch(betStatePDA);
     assert.ok(betStateBeforeClosing.status.hasOwnProperty("open"));
 
+    // A bet can only be closed once its end time has passed
+    try {
+      await program.rpc.closeBetState({
+        accounts: {
+          betState: betStatePDA,
+          betCreator: providerWallet.publicKey
+        }
+      });
+      assert.fail("The bet was closed before its end time");
+    } catch (error) {
+      assert.equal(error.msg, 'Cannot close the bet before its end time.');
+    }
+
+    await sleepUntil(betStateBeforeClosing.endTime.toNumber());
+
     await program.rpc.closeBetState(
-      end_time,
       {
         accounts: {
-          betState: betStateKP.publicKey,
+          betState: betStatePDA,
           betCreator: providerWallet.publicKey
         }
       }
     )
 
-    const betStateAfterClosing = await program.account.betState.fetch(betStateKP.publicKey);
+    const betStateAfterClosing = await program.account.betState.fetch(betStatePDA);
     assert.ok(betStateAfterClosing);
     assert.ok(betStateAfterClosing.betOutcome.hasOwnProperty("undecided"));
     assert.ok(betStateAfterClosing.status.hasOwnProperty("closed"));
@@ -669,19 +898,11 @@ describe('juicy-bets', () => {
 
 
 
-    ///// ***** DECIDE THE BET STATE OUTCOME ***** /////
+    ///// ***** PROPOSE AND FINALIZE THE BET STATE OUTCOME ***** /////
 
-    await program.rpc.decideBetStateOutcome(
-      1,
-      {
-        accounts: {
-          betState: betStateKP.publicKey,
-          betCreator: providerWallet.publicKey
-        }
-      }
-    )
+    await proposeAndFinalizeOutcome(betStatePDA, 1);
 
-    const betStateAfterBetOutcomeDecided = await program.account.betState.fetch(betStateKP.publicKey);
+    const betStateAfterBetOutcomeDecided = await program.account.betState.fetch(betStatePDA);
     assert.ok(betStateAfterBetOutcomeDecided);
     assert.ok(betStateAfterBetOutcomeDecided.betOutcome.hasOwnProperty("partyOneWin"));
 
@@ -701,8 +922,8 @@ describe('juicy-bets', () => {
 
     /// CHECK CURRENT BET STATE TO SEE ALL THREE BETTORS AND CONFIRM POOL TOTALS ///
     console.log("*****BETTOR STATE INFO*****");
-    const betStateAfterThreeBets = await program.account.betState.fetch(betStateKP.publicKey);
-    console.log(`Bet State actual sol balance after 3 bets: ${(await program.provider.connection.getBalance(betStateKP.publicKey))/LAMPORTS_PER_SOL} SOL`);
+    const betStateAfterThreeBets = await program.account.betState.fetch(betStatePDA);
+    console.log(`Bet vault actual sol balance after 3 bets: ${(await program.provider.connection.getBalance(await betVaultFor(betStatePDA)))/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State party 1 pool balance after 3 bets: ${betStateAfterThreeBets.partyOnePool.toNumber()/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State party 2 pool balance after 3 bets: ${betStateAfterThreeBets.partyTwoPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
      
@@ -718,8 +939,8 @@ describe('juicy-bets', () => {
 
     console.log("*****PRE BETTOR 1 CLAIM STATE******");
     console.log(`Bet Creator Sol Balance `)
-    console.log(`Bet State Sol Balance before Bettor 1 (Bet Creator) claims winnings: ${(await program.provider.connection.getBalance(betStateKP.publicKey))/LAMPORTS_PER_SOL} SOL`);
-    console.log(`Bettor 1 (User 1) User Account Sol Balance Pre-Claim: ${(await program.provider.connection.getBalance(user1AccountKP.publicKey))/LAMPORTS_PER_SOL} SOL`);  
+    console.log(`Bet vault Sol Balance before Bettor 1 (Bet Creator) claims winnings: ${(await program.provider.connection.getBalance(await betVaultFor(betStatePDA)))/LAMPORTS_PER_SOL} SOL`);
+    console.log(`Bettor 1 (User 1) User vault Sol Balance Pre-Claim: ${(await program.provider.connection.getBalance(await accountVaultFor(user1AccountPDA)))/LAMPORTS_PER_SOL} SOL`);  
     console.log(`Bet State Account Static Total before Bettor 1 (User 1) claims winnings: ${betStateAfterThreeBets.staticTotalPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State Account Running Total before Bettor 1 (User 1) claims winnings: ${betStateAfterThreeBets.runningTotalPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
 
@@ -730,24 +951,31 @@ describe('juicy-bets', () => {
 
     // TODO: Abstract into utils
     await program.rpc.claimWinnings(
-      bettor1Winnings,
       {
         accounts: {
-          betState: betStateKP.publicKey,
-          wagerDetail: wagerDetail1KP.publicKey,
-          userAccount: user1AccountKP.publicKey,
-          bettor: user1KP.publicKey
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          wagerDetail: wagerDetail1PDA,
+          userAccount: user1AccountPDA,
+          accountVault: await accountVaultFor(user1AccountPDA),
+          bettor: user1KP.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId
         },
         signers:[user1KP]
       }
     )
 
-    const betStateAfterUser1Claims = await program.account.betState.fetch(betStateKP.publicKey);
+    const betStateAfterUser1Claims = await program.account.betState.fetch(betStatePDA);
     assert.ok(betStateAfterUser1Claims);
 
+    const user1AccountAfterClaim = await program.account.userAccount.fetch(user1AccountPDA);
+    assert.ok(user1AccountAfterClaim.wins.eq(new anchor.BN(1)));
+    assert.ok(user1AccountAfterClaim.realizedProfit.gt(new anchor.BN(0)));
+
     console.log("*****POST BETTOR 1 CLAIM STATE******");
-    console.log(`Bet State Sol Balance after Bettor 1 (User 1) claims winnings: ${(await program.provider.connection.getBalance(betStateKP.publicKey))/LAMPORTS_PER_SOL} SOL`);
-    console.log(`Bettor 1 (User 1) User Account Sol Balance Post-Claim: ${(await program.provider.connection.getBalance(user1AccountKP.publicKey))/LAMPORTS_PER_SOL} SOL`);
+    console.log(`Bet vault Sol Balance after Bettor 1 (User 1) claims winnings: ${(await program.provider.connection.getBalance(await betVaultFor(betStatePDA)))/LAMPORTS_PER_SOL} SOL`);
+    console.log(`Bettor 1 (User 1) User vault Sol Balance Post-Claim: ${(await program.provider.connection.getBalance(await accountVaultFor(user1AccountPDA)))/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State Account Static Total after Bettor 1 (User 1) claims winnings: ${betStateAfterUser1Claims.staticTotalPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State Account Running Total after Bettor 1 (User 1) claims winnings: ${betStateAfterUser1Claims.runningTotalPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
 
@@ -757,161 +985,1184 @@ describe('juicy-bets', () => {
     ///// ***** LAST BETTOR CLAIMS WINNINGS ***** /////
 
     await program.rpc.claimWinnings(
-      bettor2Winnings,
       {
         accounts: {
-          betState: betStateKP.publicKey,
-          wagerDetail: wagerDetail2KP.publicKey,
-          userAccount: user2AccountKP.publicKey,
-          bettor: user2KP.publicKey
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          wagerDetail: wagerDetail2PDA,
+          userAccount: user2AccountPDA,
+          accountVault: await accountVaultFor(user2AccountPDA),
+          bettor: user2KP.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId
         },
         signers:[user2KP]
       }
     );
 
-    const betStateAfterUser2Claims = await program.account.betState.fetch(betStateKP.publicKey);
+    const betStateAfterUser2Claims = await program.account.betState.fetch(betStatePDA);
     assert.ok(betStateAfterUser2Claims);
 
+    // The last winning claim sweeps any rounding dust, leaving nothing in the pool
+    assert.ok(betStateAfterUser2Claims.runningTotalPool.eq(new anchor.BN(0)));
+
     console.log("*****POST FULLY CLAIMED BET STATE******");
-    console.log(`Bet State Sol Balance after all winnings are claimed: ${(await program.provider.connection.getBalance(betStateKP.publicKey))/LAMPORTS_PER_SOL} SOL`);
-    console.log(`Bettor 2 (User 2) User Account Sol Balance after all winnings are claimed: ${(await program.provider.connection.getBalance(user2AccountKP.publicKey))/LAMPORTS_PER_SOL} SOL`);
+    console.log(`Bet vault Sol Balance after all winnings are claimed: ${(await program.provider.connection.getBalance(await betVaultFor(betStatePDA)))/LAMPORTS_PER_SOL} SOL`);
+    console.log(`Bettor 2 (User 2) User vault Sol Balance after all winnings are claimed: ${(await program.provider.connection.getBalance(await accountVaultFor(user2AccountPDA)))/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State Account Static Total after all winnings are claimed: ${betStateAfterUser2Claims.staticTotalPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
     console.log(`Bet State Account Running Total after all winnings are claimed: ${betStateAfterUser2Claims.runningTotalPool.toNumber()/LAMPORTS_PER_SOL} SOL`);
 
 
 
 
-    ///// ***** BET CREATOR SETTLES BET WHEN ALL WINNINGS ARE CLAIMED ***** /////
+    ///// ***** LOSING BETTOR'S WAGER IS SETTLED BY A CRANK ***** /////
 
-    await program.rpc.settleBetState(
+    const wagerDetail3 = await program.account.wagerDetail.fetch(wagerDetail3PDA);
+
+    await program.rpc.acknowledgeLoss(
       {
         accounts: {
-          betState: betStateKP.publicKey,
-          betCreator: providerWallet.publicKey,
+          betState: betStatePDA,
+          wagerDetail: wagerDetail3PDA,
+          userAccount: user3AccountPDA,
+          bettor: user3KP.publicKey,
+          config: configPDA
         }
       }
     );
 
-    const betStateToFindAfterBetStateSettles = await program.account.betState.all([
-      {
-        memcmp: {
-          offset: 8,
-          bytes: betStateKP.publicKey.toBase58(),
-        }
-      }
-    ]);
+    const user3AccountAfterLoss = await program.account.userAccount.fetch(user3AccountPDA);
+    assert.ok(user3AccountAfterLoss.losses.eq(new anchor.BN(1)));
+    assert.ok(user3AccountAfterLoss.realizedProfit.eq(wagerDetail3.betValue.neg()));
+    assert.equal(user3AccountAfterLoss.activeWagers.length, 0);
+    assert.equal(await program.provider.connection.getAccountInfo(wagerDetail3PDA), null);
 
-    assert.equal(betStateToFindAfterBetStateSettles.length, 0);
 
-  });
 
 
-  // Placing Wagers //
+    ///// ***** BET CREATOR SETTLES BET WHEN ALL WINNINGS ARE CLAIMED ***** /////
 
-  // WILL NEED TO BE FULLY TESTED ON FRONT END
-  // Create a utility function to find bettor detail structs
-  it.skip('Cannot place multiple wagers on one bet state from the same betting user', async() => {
+    await program.rpc.settleBetState(
+      {
+        accounts: {
+          betState: betStatePDA,
+          betCreator: providerWallet.publicKey,
+        }
+      }
+    );
 
-    const betCreatorKeyPair = anchor.web3.Keypair.generate();
-    const sig = await program.provider.connection.requestAirdrop(betCreatorKeyPair.publicKey, 2000000000)
-    await program.provider.connection.confirmTransaction(sig, "finalized");
+    // The settled bet stays readable with its final accounting
+    const betStateAfterSettle = await program.account.betState.fetch(betStatePDA);
+
+    assert.ok(betStateAfterSettle.status.hasOwnProperty("settled"));
+    assert.ok(betStateAfterSettle.settledAt.gt(new anchor.BN(0)));
+    assert.ok(betStateAfterSettle.totalPaidOut.eq(betStateAfterSettle.partyOnePool.add(betStateAfterSettle.partyTwoPool)));
+    assert.ok(betStateAfterSettle.totalFees.gt(new anchor.BN(0)));
 
+    try {
+      await program.rpc.closeSettledBetState({
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          marketVault: (await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("vault"), betStatePDA.toBuffer()],
+            program.programId
+          ))[0],
+          betCreator: providerWallet.publicKey,
+          systemProgram: SystemProgram.programId
+        }
+      });
+      assert.fail("The settled bet was closed before its retention period");
+    } catch (error) {
+      assert.equal(error.msg, 'A settled bet can only be closed after its retention period.');
+    }
 
+  });
 
 
-     ///// ***** INITIALIZE BET FUNCTIONALITY ***** /////
+  it('Void a bet and refund every wager into its user account', async() => {
 
-    // Generate a new random keypair for betState
-    const betStateKP = anchor.web3.Keypair.generate();
-    const tradingPair = TICKERS[0];
+    ///// ***** INITIALIZE A SHORT BET ***** /////
 
-    const start = new anchor.BN(Date.now());
-    const duration = new anchor.BN(5 * 60 * 1000);
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+    const duration = new anchor.BN(10);
+    const symbol = 'Equity.US.AAPL/USD';
+    const betRange = 4;
 
-    console.log("Starting the 'initialize bet state' functionality...");
+    const [betStatePDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("bet"), Buffer.from(symbol), start.toArrayLike(Buffer, "le", 8), Buffer.from([betRange])],
+      program.programId
+    );
 
     await program.rpc.initializeBetState(
       start,
       duration,
-      tradingPair,
+      symbol,
+      new anchor.BN(165.3 * 1000),
+      betRange,
+      providerWallet.publicKey,
+      providerWallet.publicKey,
+      CLAIM_WINDOW,
+      DISPUTE_WINDOW,
       {
         accounts: {
-          betState: betStateKP.publicKey,
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
           betCreator: providerWallet.publicKey,
+          priceFeed: PRICE_FEED,
+          config: configPDA,
           systemProgram: anchor.web3.SystemProgram.programId
-        },
-        signers:[betStateKP]
+        }
       },
     )
 
-    let betStateAccount = await program.account.betState.fetch(betStateKP.publicKey);
-
-    console.log(`${JSON.stringify(betStateAccount)}`);
-
-    assert.ok(betStateAccount);
-    assert.ok(betStateAccount.staticTotalPool.eq(new anchor.BN(0)));
-    assert.ok(betStateAccount.runningTotalPool.eq(new anchor.BN(0)));
-    assert.ok(betStateAccount.partyOnePool.eq(new anchor.BN(0)));
-    assert.ok(betStateAccount.partyTwoPool.eq(new anchor.BN(0)));
-    assert.ok(betStateAccount.status.hasOwnProperty("open"));
-    assert.ok(betStateAccount.betOutcome.hasOwnProperty("undecided"));
-
-
 
-            ///// USER 1 /////
 
 
+    ///// ***** USER CREATES AN ACCOUNT, DEPOSITS AND WAGERS ***** /////
 
+    const userKP = anchor.web3.Keypair.generate();
+    const airdrop_sig = await program.provider.connection.requestAirdrop(userKP.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(airdrop_sig, "finalized");
 
-    ///// ***** USER 1 CREATES ACCOUNT ***** /////
+    const [userAccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user"), userKP.publicKey.toBuffer()],
+      program.programId
+    );
 
-    const user1KP = anchor.web3.Keypair.generate();
-    const bettor1_airdrop_sig = await program.provider.connection.requestAirdrop(user1KP.publicKey, 2000000000)
-    await program.provider.connection.confirmTransaction(bettor1_airdrop_sig, "finalized");
+    await program.rpc.initializeUserAccount({
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        accountOwner: userKP.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [userKP]
+    })
 
-    const user1AccountKP = anchor.web3.Keypair.generate();
+    await program.rpc.depositIntoAccount(new anchor.BN(LAMPORTS_PER_SOL * 1), {
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        accountOwner: userKP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers:[userKP]
+    })
 
-    console.log("Initializaing User 1 Account...");
+    const party = 1;
+    const [wagerDetailPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("wager"), betStatePDA.toBuffer(), userKP.publicKey.toBuffer(), Buffer.from([party])],
+      program.programId
+    );
 
-    await program.rpc.initializeUserAccount({
+    await program.rpc.placeWager(party, new anchor.BN((LAMPORTS_PER_SOL * 0.5) * JUICED_BETS_TAKE_RATE), {
       accounts: {
-        userAccount: user1AccountKP.publicKey,
-        accountOwner: user1KP.publicKey,
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        wagerDetail: wagerDetailPDA,
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        bettorAccount: userKP.publicKey,
+        config: configPDA,
+        treasury: treasuryPDA,
         systemProgram: anchor.web3.SystemProgram.programId
       },
-      signers: [user1KP, user1AccountKP]
+      signers: [userKP]
     })
 
-    const userAccount = await program.account.userAccount.fetch(user1AccountKP.publicKey);
+    const wagerDetail = await program.account.wagerDetail.fetch(wagerDetailPDA);
+    const userAccountAfterWager = await program.account.userAccount.fetch(userAccountPDA);
 
-    assert.ok(userAccount);
-    console.log(`Bettor 1 User Account: ${JSON.stringify(userAccount)}`);
-    assert.equal(userAccount.currentBalance.toNumber(), 0)
-    assert.equal(userAccount.wins.toNumber(), 0)
-    assert.equal(userAccount.losses.toNumber(), 0)
 
 
 
+    ///// ***** BET IS CLOSED AND VOIDED ***** /////
 
-    ///// ***** USER 1 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
+    await sleepUntil((await program.account.betState.fetch(betStatePDA)).endTime.toNumber());
 
-    const user1_lamports_to_deposit_num = LAMPORTS_PER_SOL * 1
-    const user1_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
+    await program.rpc.closeBetState({
+      accounts: {
+        betState: betStatePDA,
+        betCreator: providerWallet.publicKey
+      }
+    })
 
-    console.log("User 1 Depositing...");
+    await proposeAndFinalizeOutcome(betStatePDA, 3);
 
-    await program.rpc.depositIntoAccount(user1_lamports_to_deposit, {
+    const betStateAfterVoid = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betStateAfterVoid.betOutcome.hasOwnProperty("void"));
+
+
+
+
+    ///// ***** VOIDED BETS CANNOT BE CLAIMED, ONLY REFUNDED ***** /////
+
+    try {
+      await program.rpc.claimWinnings({
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          wagerDetail: wagerDetailPDA,
+          userAccount: userAccountPDA,
+          accountVault: await accountVaultFor(userAccountPDA),
+          bettor: userKP.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId
+        },
+        signers:[userKP]
+      })
+      assert.fail("Claiming winnings from a voided bet should fail");
+    } catch (error) {
+      assert.equal(error.msg, 'Cannot carry out this action because the bet was voided. Refund your wager instead.');
+    }
+
+    await program.rpc.refundWager({
+      accounts: {
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        wagerDetail: wagerDetailPDA,
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        bettor: userKP.publicKey,
+        config: configPDA,
+        systemProgram: SystemProgram.programId
+      },
+      signers:[userKP]
+    })
+
+    const betStateAfterRefund = await program.account.betState.fetch(betStatePDA);
+    const userAccountAfterRefund = await program.account.userAccount.fetch(userAccountPDA);
+
+    assert.ok(betStateAfterRefund.runningTotalPool.eq(new anchor.BN(0)));
+    assert.ok(userAccountAfterRefund.currentBalance.eq(userAccountAfterWager.currentBalance.add(wagerDetail.betValue)));
+    assert.equal(userAccountAfterRefund.activeWagers.length, 0);
+    assert.equal(await program.provider.connection.getAccountInfo(wagerDetailPDA), null);
+
+  });
+
+
+  it('A token market takes wagers, fees and payouts in a locally created mint', async() => {
+
+    ///// ***** CREATE A MINT AND THE TREASURY VAULT FOR IT ***** /////
+
+    const mintAuthorityKP = anchor.web3.Keypair.generate();
+    const mint_airdrop_sig = await program.provider.connection.requestAirdrop(mintAuthorityKP.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(mint_airdrop_sig, "finalized");
+
+    const collateralMint = await Token.createMint(
+      program.provider.connection,
+      mintAuthorityKP,
+      mintAuthorityKP.publicKey,
+      null,
+      6,
+      TOKEN_PROGRAM_ID
+    );
+
+    const [treasuryVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_vault"), collateralMint.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.rpc.initializeTreasuryVault({
+      accounts: {
+        treasury: treasuryPDA,
+        treasuryVault: treasuryVaultPDA,
+        collateralMint: collateralMint.publicKey,
+        authority: providerWallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY
+      }
+    })
+
+
+
+
+    ///// ***** INITIALIZE A SHORT BET SETTLED IN THE MINT ***** /////
+
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+    const duration = new anchor.BN(10);
+    const symbol = 'Equity.US.MSFT/USD';
+    const betRange = 5;
+
+    const [betStatePDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("bet"), Buffer.from(symbol), start.toArrayLike(Buffer, "le", 8), Buffer.from([betRange])],
+      program.programId
+    );
+
+    const [marketVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vault"), betStatePDA.toBuffer()],
+      program.programId
+    );
+
+    await program.rpc.initializeTokenBetState(
+      start,
+      duration,
+      symbol,
+      new anchor.BN(280.4 * 1000),
+      betRange,
+      providerWallet.publicKey,
+      providerWallet.publicKey,
+      CLAIM_WINDOW,
+      DISPUTE_WINDOW,
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          marketVault: marketVaultPDA,
+          collateralMint: collateralMint.publicKey,
+          betCreator: providerWallet.publicKey,
+          priceFeed: PRICE_FEED,
+          config: configPDA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY
+        }
+      },
+    )
+
+    const betState = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betState.collateralMint.equals(collateralMint.publicKey));
+
+
+
+
+    ///// ***** TWO USERS DEPOSIT TOKENS AND WAGER ON OPPOSITE SIDES ***** /////
+
+    const tokens_to_wager = new anchor.BN(102_000_000);
+    const bettors = [];
+
+    for (const party of [1, 2]) {
+      const userKP = anchor.web3.Keypair.generate();
+      const airdrop_sig = await program.provider.connection.requestAirdrop(userKP.publicKey, 2000000000)
+      await program.provider.connection.confirmTransaction(airdrop_sig, "finalized");
+
+      const [userAccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("user"), userKP.publicKey.toBuffer()],
+        program.programId
+      );
+      const [userVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("user_vault"), userAccountPDA.toBuffer(), collateralMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [wagerDetailPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("wager"), betStatePDA.toBuffer(), userKP.publicKey.toBuffer(), Buffer.from([party])],
+        program.programId
+      );
+
+      await program.rpc.initializeUserAccount({
+        accounts: {
+          userAccount: userAccountPDA,
+          accountVault: await accountVaultFor(userAccountPDA),
+          accountOwner: userKP.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers: [userKP]
+      })
+
+      await program.rpc.initializeUserVault({
+        accounts: {
+          userAccount: userAccountPDA,
+          userVault: userVaultPDA,
+          collateralMint: collateralMint.publicKey,
+          accountOwner: userKP.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY
+        },
+        signers: [userKP]
+      })
+
+      const walletTokenAccount = await collateralMint.createAccount(userKP.publicKey);
+      await collateralMint.mintTo(walletTokenAccount, mintAuthorityKP, [], tokens_to_wager.toNumber());
+
+      await program.rpc.depositTokensIntoAccount(tokens_to_wager, {
+        accounts: {
+          userAccount: userAccountPDA,
+          userVault: userVaultPDA,
+          source: walletTokenAccount,
+          accountOwner: userKP.publicKey,
+          config: configPDA,
+          tokenProgram: TOKEN_PROGRAM_ID
+        },
+        signers: [userKP]
+      })
+
+      await program.rpc.placeTokenWager(party, tokens_to_wager, {
+        accounts: {
+          betState: betStatePDA,
+          wagerDetail: wagerDetailPDA,
+          userAccount: userAccountPDA,
+          userVault: userVaultPDA,
+          marketVault: marketVaultPDA,
+          bettorAccount: userKP.publicKey,
+          config: configPDA,
+          treasuryVault: treasuryVaultPDA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers: [userKP]
+      })
+
+      bettors.push({ userKP, userAccountPDA, userVaultPDA, wagerDetailPDA, walletTokenAccount });
+    }
+
+    // 2% of each wager goes to the treasury's vault, the rest to the market's vault
+    assert.ok((await collateralMint.getAccountInfo(marketVaultPDA)).amount.eq(new anchor.BN(200_000_000)));
+    assert.ok((await collateralMint.getAccountInfo(treasuryVaultPDA)).amount.eq(new anchor.BN(4_000_000)));
+
+    const betStateAfterWagers = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betStateAfterWagers.partyOnePool.eq(new anchor.BN(100_000_000)));
+    assert.ok(betStateAfterWagers.totalFees.eq(new anchor.BN(4_000_000)));
+
+    // Lamport instructions reject token markets
+    try {
+      await program.rpc.placeWager(1, new anchor.BN(LAMPORTS_PER_SOL), {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          wagerDetail: bettors[0].wagerDetailPDA,
+          userAccount: bettors[0].userAccountPDA,
+          accountVault: await accountVaultFor(bettors[0].userAccountPDA),
+          bettorAccount: bettors[0].userKP.publicKey,
+          config: configPDA,
+          treasury: treasuryPDA,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers: [bettors[0].userKP]
+      })
+      assert.fail("A lamport wager on a token market should fail");
+    } catch (error) {
+      assert.equal(error.msg, 'This instruction does not match the collateral the bet is settled in.');
+    }
+
+
+
+
+    ///// ***** PARTY 1 WINS AND CLAIMS THE WHOLE POOL IN TOKENS ***** /////
+
+    await sleepUntil((await program.account.betState.fetch(betStatePDA)).endTime.toNumber());
+
+    await program.rpc.closeBetState({
+      accounts: {
+        betState: betStatePDA,
+        betCreator: providerWallet.publicKey
+      }
+    })
+
+    await proposeAndFinalizeOutcome(betStatePDA, 1);
+
+    const winner = bettors[0];
+
+    await program.rpc.claimTokenWinnings({
+      accounts: {
+        betState: betStatePDA,
+        wagerDetail: winner.wagerDetailPDA,
+        userAccount: winner.userAccountPDA,
+        userVault: winner.userVaultPDA,
+        marketVault: marketVaultPDA,
+        bettor: winner.userKP.publicKey,
+        config: configPDA,
+        tokenProgram: TOKEN_PROGRAM_ID
+      },
+      signers: [winner.userKP]
+    })
+
+    assert.ok((await collateralMint.getAccountInfo(winner.userVaultPDA)).amount.eq(new anchor.BN(200_000_000)));
+    assert.ok((await collateralMint.getAccountInfo(marketVaultPDA)).amount.eq(new anchor.BN(0)));
+
+    await program.rpc.withdrawTokensFromAccount(new anchor.BN(200_000_000), {
+      accounts: {
+        userAccount: winner.userAccountPDA,
+        userVault: winner.userVaultPDA,
+        destination: winner.walletTokenAccount,
+        accountOwner: winner.userKP.publicKey,
+        config: configPDA,
+        tokenProgram: TOKEN_PROGRAM_ID
+      },
+      signers: [winner.userKP]
+    })
+
+    assert.ok((await collateralMint.getAccountInfo(winner.walletTokenAccount)).amount.eq(new anchor.BN(200_000_000)));
+
+
+
+
+    ///// ***** SETTLE THE BET AND CLOSE ITS VAULT ***** /////
+
+    await program.rpc.settleBetState({
+      accounts: {
+        betState: betStatePDA,
+        betCreator: providerWallet.publicKey
+      }
+    })
+
+    await program.rpc.closeMarketVault({
+      accounts: {
+        betState: betStatePDA,
+        marketVault: marketVaultPDA,
+        treasuryVault: treasuryVaultPDA,
+        betCreator: providerWallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID
+      }
+    })
+
+    assert.equal(await program.provider.connection.getAccountInfo(marketVaultPDA), null);
+
+  });
+
+
+  it('Creator cancels an open bet and a crank refunds every wager and closes the bet', async() => {
+
+    ///// ***** INITIALIZE BET FUNCTIONALITY ***** /////
+
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+    const duration = new anchor.BN(2 * 60);
+    const symbol = 'Equity.US.TSLA/USD';
+    const betRange = 7;
+
+    const [betStatePDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("bet"), Buffer.from(symbol), start.toArrayLike(Buffer, "le", 8), Buffer.from([betRange])],
+      program.programId
+    );
+
+    await program.rpc.initializeBetState(
+      start,
+      duration,
+      symbol,
+      new anchor.BN(870.1 * 1000),
+      betRange,
+      providerWallet.publicKey,
+      providerWallet.publicKey,
+      CLAIM_WINDOW,
+      DISPUTE_WINDOW,
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          betCreator: providerWallet.publicKey,
+          priceFeed: PRICE_FEED,
+          config: configPDA,
+          systemProgram: anchor.web3.SystemProgram.programId
+        }
+      },
+    )
+
+
+
+
+    ///// ***** TWO USERS WAGER ON OPPOSITE SIDES ***** /////
+
+    const bettors = [];
+
+    for (const party of [1, 2]) {
+      const userKP = anchor.web3.Keypair.generate();
+      const airdrop_sig = await program.provider.connection.requestAirdrop(userKP.publicKey, 2000000000)
+      await program.provider.connection.confirmTransaction(airdrop_sig, "finalized");
+
+      const [userAccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("user"), userKP.publicKey.toBuffer()],
+        program.programId
+      );
+      const [wagerDetailPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("wager"), betStatePDA.toBuffer(), userKP.publicKey.toBuffer(), Buffer.from([party])],
+        program.programId
+      );
+
+      await program.rpc.initializeUserAccount({
+        accounts: {
+          userAccount: userAccountPDA,
+          accountVault: await accountVaultFor(userAccountPDA),
+          accountOwner: userKP.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers: [userKP]
+      })
+
+      await program.rpc.depositIntoAccount(new anchor.BN(LAMPORTS_PER_SOL * 1), {
+        accounts: {
+          userAccount: userAccountPDA,
+          accountVault: await accountVaultFor(userAccountPDA),
+          accountOwner: userKP.publicKey,
+          config: configPDA,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers:[userKP]
+      })
+
+      await program.rpc.placeWager(party, new anchor.BN((LAMPORTS_PER_SOL * 0.5) * JUICED_BETS_TAKE_RATE), {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          wagerDetail: wagerDetailPDA,
+          userAccount: userAccountPDA,
+          accountVault: await accountVaultFor(userAccountPDA),
+          bettorAccount: userKP.publicKey,
+          config: configPDA,
+          treasury: treasuryPDA,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers: [userKP]
+      })
+
+      bettors.push({
+        userKP,
+        userAccountPDA,
+        accountVaultPDA: await accountVaultFor(userAccountPDA),
+        wagerDetailPDA,
+        betValue: (await program.account.wagerDetail.fetch(wagerDetailPDA)).betValue,
+        balanceAfterWager: (await program.account.userAccount.fetch(userAccountPDA)).currentBalance,
+      });
+    }
+
+
+
+
+    ///// ***** CREATOR CANCELS THE OPEN BET ***** /////
+
+    await program.rpc.cancelBetState({
+      accounts: {
+        betState: betStatePDA,
+        betCreator: providerWallet.publicKey,
+        config: configPDA
+      }
+    })
+
+    const betStateAfterCancel = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betStateAfterCancel.status.hasOwnProperty("cancelled"));
+    assert.ok(betStateAfterCancel.betOutcome.hasOwnProperty("void"));
+
+
+
+
+    ///// ***** ANYONE CAN CRANK THE REFUNDS ***** /////
+
+    await program.rpc.refundWagers({
+      accounts: {
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        betCreator: providerWallet.publicKey,
+        config: configPDA,
+        systemProgram: SystemProgram.programId
+      },
+      remainingAccounts: bettors.flatMap(({ userKP, userAccountPDA, accountVaultPDA, wagerDetailPDA }) => [
+        { pubkey: wagerDetailPDA, isWritable: true, isSigner: false },
+        { pubkey: userAccountPDA, isWritable: true, isSigner: false },
+        { pubkey: accountVaultPDA, isWritable: true, isSigner: false },
+        { pubkey: userKP.publicKey, isWritable: true, isSigner: false },
+      ])
+    })
+
+    const vaultRent = await program.provider.connection.getMinimumBalanceForRentExemption(0);
+
+    for (const { userAccountPDA, accountVaultPDA, wagerDetailPDA, betValue, balanceAfterWager } of bettors) {
+      const userAccountAfterRefund = await program.account.userAccount.fetch(userAccountPDA);
+
+      assert.ok(userAccountAfterRefund.currentBalance.eq(balanceAfterWager.add(betValue)));
+      assert.equal(await program.provider.connection.getBalance(accountVaultPDA), vaultRent + userAccountAfterRefund.currentBalance.toNumber());
+      assert.equal(userAccountAfterRefund.activeWagers.length, 0);
+      assert.equal(await program.provider.connection.getAccountInfo(wagerDetailPDA), null);
+    }
+
+    // The bet state and its vault are closed once the last wager is refunded
+    assert.equal(await program.provider.connection.getAccountInfo(betStatePDA), null);
+    assert.equal(await program.provider.connection.getAccountInfo(await betVaultFor(betStatePDA)), null);
+
+  });
+
+
+  it('Unclaimed winnings are swept into the treasury after the claim deadline', async() => {
+
+    ///// ***** INITIALIZE A SHORT BET WITH THE SHORTEST CLAIM WINDOW ***** /////
+
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+    const duration = new anchor.BN(10);
+    const symbol = 'Equity.US.SPY/USD';
+    const betRange = 5;
+
+    const [betStatePDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("bet"), Buffer.from(symbol), start.toArrayLike(Buffer, "le", 8), Buffer.from([betRange])],
+      program.programId
+    );
+
+    await program.rpc.initializeBetState(
+      start,
+      duration,
+      symbol,
+      new anchor.BN(725.45 * 1000),
+      betRange,
+      providerWallet.publicKey,
+      providerWallet.publicKey,
+      MIN_CLAIM_WINDOW,
+      DISPUTE_WINDOW,
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          betCreator: providerWallet.publicKey,
+          priceFeed: PRICE_FEED,
+          config: configPDA,
+          systemProgram: anchor.web3.SystemProgram.programId
+        }
+      },
+    )
+
+
+
+
+    ///// ***** USER CREATES AN ACCOUNT, DEPOSITS AND WAGERS ***** /////
+
+    const userKP = anchor.web3.Keypair.generate();
+    const airdrop_sig = await program.provider.connection.requestAirdrop(userKP.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(airdrop_sig, "finalized");
+
+    const [userAccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user"), userKP.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.rpc.initializeUserAccount({
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        accountOwner: userKP.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [userKP]
+    })
+
+    await program.rpc.depositIntoAccount(new anchor.BN(LAMPORTS_PER_SOL * 1), {
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        accountOwner: userKP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers:[userKP]
+    })
+
+    const party = 1;
+    const [wagerDetailPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("wager"), betStatePDA.toBuffer(), userKP.publicKey.toBuffer(), Buffer.from([party])],
+      program.programId
+    );
+
+    await program.rpc.placeWager(party, new anchor.BN((LAMPORTS_PER_SOL * 0.5) * JUICED_BETS_TAKE_RATE), {
+      accounts: {
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        wagerDetail: wagerDetailPDA,
+        userAccount: userAccountPDA,
+        accountVault: await accountVaultFor(userAccountPDA),
+        bettorAccount: userKP.publicKey,
+        config: configPDA,
+        treasury: treasuryPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [userKP]
+    })
+
+
+
+
+    ///// ***** BET IS CLOSED AND DECIDED, THE CLAIM DEADLINE PASSES ***** /////
+
+    const betStateBeforeClose = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betStateBeforeClose.claimDeadline.eq(new anchor.BN(0)));
+
+    await sleepUntil(betStateBeforeClose.endTime.toNumber());
+
+    await program.rpc.closeBetState({
+      accounts: {
+        betState: betStatePDA,
+        betCreator: providerWallet.publicKey
+      }
+    })
+
+    await proposeAndFinalizeOutcome(betStatePDA, 1);
+
+    // The claim window only starts once the outcome is final
+    const betStateAfterFinalize = await program.account.betState.fetch(betStatePDA);
+    const disputeDeadline = betStateAfterFinalize.proposedAt.add(betStateAfterFinalize.disputeWindow);
+    assert.ok(betStateAfterFinalize.claimDeadline.gt(disputeDeadline.add(MIN_CLAIM_WINDOW)));
+
+    await sleepUntil(betStateAfterFinalize.claimDeadline.toNumber());
+
+    try {
+      await program.rpc.claimWinnings({
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          wagerDetail: wagerDetailPDA,
+          userAccount: userAccountPDA,
+          accountVault: await accountVaultFor(userAccountPDA),
+          bettor: userKP.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId
+        },
+        signers:[userKP]
+      })
+      assert.fail("Winnings were claimed after the claim deadline");
+    } catch (error) {
+      assert.equal(error.msg, 'The claim window for this bet has expired.');
+    }
+
+
+
+
+    ///// ***** CREATOR SWEEPS THE UNCLAIMED WINNINGS ***** /////
+
+    const betStateBeforeSweep = await program.account.betState.fetch(betStatePDA);
+    const treasuryBeforeSweep = await program.account.treasury.fetch(treasuryPDA);
+
+    await program.rpc.sweepUnclaimedFunds({
+      accounts: {
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        treasury: treasuryPDA,
+        authority: providerWallet.publicKey,
+        config: configPDA,
+        systemProgram: SystemProgram.programId
+      }
+    })
+
+    const betStateAfterSweep = await program.account.betState.fetch(betStatePDA);
+    const treasuryAfterSweep = await program.account.treasury.fetch(treasuryPDA);
+
+    assert.ok(betStateAfterSweep.status.hasOwnProperty("settled"));
+    assert.ok(betStateAfterSweep.runningTotalPool.eq(new anchor.BN(0)));
+    assert.ok(betStateAfterSweep.unclaimedSwept.eq(betStateBeforeSweep.runningTotalPool));
+    assert.ok(treasuryAfterSweep.totalSwept.eq(treasuryBeforeSweep.totalSwept.add(betStateBeforeSweep.runningTotalPool)));
+
+  });
+
+
+  it('A disputed outcome proposal is overturned by the arbitrator, who awards both bonds to the disputer', async() => {
+
+    ///// ***** INITIALIZE AND CLOSE A SHORT BET ***** /////
+
+    const start = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+    const duration = new anchor.BN(5);
+    const symbol = 'Equity.US.AAPL/USD';
+    const betRange = 2;
+
+    const [betStatePDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("bet"), Buffer.from(symbol), start.toArrayLike(Buffer, "le", 8), Buffer.from([betRange])],
+      program.programId
+    );
+
+    await program.rpc.initializeBetState(
+      start,
+      duration,
+      symbol,
+      new anchor.BN(165.3 * 1000),
+      betRange,
+      providerWallet.publicKey,
+      providerWallet.publicKey,
+      CLAIM_WINDOW,
+      new anchor.BN(60),
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          betCreator: providerWallet.publicKey,
+          priceFeed: PRICE_FEED,
+          config: configPDA,
+          systemProgram: anchor.web3.SystemProgram.programId
+        }
+      },
+    )
+
+    await sleepUntil((await program.account.betState.fetch(betStatePDA)).endTime.toNumber());
+
+    await program.rpc.closeBetState({
+      accounts: {
+        betState: betStatePDA,
+        betCreator: providerWallet.publicKey
+      }
+    })
+
+
+
+
+    ///// ***** RESOLVER PROPOSES, A DISPUTER CHALLENGES ***** /////
+
+    await program.rpc.proposeBetStateOutcome(
+      1,
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          resolver: providerWallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId
+        }
+      }
+    )
+
+    const disputerKP = anchor.web3.Keypair.generate();
+    const airdrop_sig = await program.provider.connection.requestAirdrop(disputerKP.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(airdrop_sig, "finalized");
+
+    await program.rpc.disputeBetStateOutcome({
+      accounts: {
+        betState: betStatePDA,
+        betVault: await betVaultFor(betStatePDA),
+        disputer: disputerKP.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [disputerKP]
+    })
+
+    const betStateAfterDispute = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betStateAfterDispute.resolution.hasOwnProperty("disputed"));
+    assert.ok(betStateAfterDispute.disputer.equals(disputerKP.publicKey));
+
+    // A disputed proposal cannot be finalized, only arbitrated
+    try {
+      await program.rpc.finalizeBetStateOutcome({
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          proposer: providerWallet.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId
+        }
+      })
+      assert.fail("A disputed proposal was finalized");
+    } catch (error) {
+      assert.equal(error.msg, 'There is no undisputed outcome proposal for this bet.');
+    }
+
+
+
+
+    ///// ***** ARBITRATOR OVERTURNS THE PROPOSAL ***** /////
+
+    const disputerBalanceBeforeArbitration = await program.provider.connection.getBalance(disputerKP.publicKey);
+
+    await program.rpc.arbitrateBetStateOutcome(
+      2,
+      {
+        accounts: {
+          betState: betStatePDA,
+          betVault: await betVaultFor(betStatePDA),
+          arbitrator: ARBITRATOR.publicKey,
+          proposer: providerWallet.publicKey,
+          disputer: disputerKP.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId
+        },
+        signers: [ARBITRATOR]
+      }
+    )
+
+    const betStateAfterArbitration = await program.account.betState.fetch(betStatePDA);
+    assert.ok(betStateAfterArbitration.resolution.hasOwnProperty("finalized"));
+    assert.ok(betStateAfterArbitration.betOutcome.hasOwnProperty("partyTwoWin"));
+    assert.equal(
+      await program.provider.connection.getBalance(disputerKP.publicKey),
+      disputerBalanceBeforeArbitration + 2 * 0.1 * LAMPORTS_PER_SOL
+    );
+
+  });
+
+
+  // User Account Tests //
+
+  it('Withdrawals stop at the vault\'s rent-exempt minimum and sync_balance picks up lamports sent to the vault', async() => {
+
+    const userKP = anchor.web3.Keypair.generate();
+    const airdrop_sig = await program.provider.connection.requestAirdrop(userKP.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(airdrop_sig, "finalized");
+
+    const [userAccountPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user"), userKP.publicKey.toBuffer()],
+      program.programId
+    );
+    const accountVaultPDA = await accountVaultFor(userAccountPDA);
+    const vaultRent = await program.provider.connection.getMinimumBalanceForRentExemption(0);
+
+    await program.rpc.initializeUserAccount({
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: accountVaultPDA,
+        accountOwner: userKP.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [userKP]
+    })
+
+    const depositSig = await program.rpc.depositIntoAccount(new anchor.BN(LAMPORTS_PER_SOL * 0.5), {
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: accountVaultPDA,
+        accountOwner: userKP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [userKP]
+    })
+
+    const [depositEvent] = await eventsEmittedBy(depositSig);
+    assert.equal(depositEvent.name, "Deposit");
+    assert.ok(depositEvent.data.userAccount.equals(userAccountPDA));
+    assert.ok(depositEvent.data.mint.equals(anchor.web3.PublicKey.default));
+    assert.equal(depositEvent.data.amount.toNumber(), LAMPORTS_PER_SOL * 0.5);
+    assert.equal(depositEvent.data.balance.toNumber(), LAMPORTS_PER_SOL * 0.5);
+
+    // Lamports sent straight to the vault are only part of the balance once it is synced
+    const transferTX = new anchor.web3.Transaction().add(
+      SystemProgram.transfer({ fromPubkey: userKP.publicKey, toPubkey: accountVaultPDA, lamports: LAMPORTS_PER_SOL * 0.25 })
+    );
+    await program.provider.send(transferTX, [userKP]);
+
+    const userAccountBeforeSync = await program.account.userAccount.fetch(userAccountPDA);
+    assert.equal(userAccountBeforeSync.currentBalance.toNumber(), LAMPORTS_PER_SOL * 0.5);
+
+    const syncSig = await program.rpc.syncBalance({
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: accountVaultPDA,
+        accountOwner: userKP.publicKey,
+        config: configPDA
+      },
+      signers: [userKP]
+    })
+
+    const [syncEvent] = await eventsEmittedBy(syncSig);
+    assert.equal(syncEvent.name, "BalanceSynced");
+    assert.equal(syncEvent.data.oldBalance.toNumber(), LAMPORTS_PER_SOL * 0.5);
+    assert.equal(syncEvent.data.newBalance.toNumber(), LAMPORTS_PER_SOL * 0.75);
+
+    const userAccountAfterSync = await program.account.userAccount.fetch(userAccountPDA);
+    assert.equal(userAccountAfterSync.currentBalance.toNumber(), LAMPORTS_PER_SOL * 0.75);
+
+    const withdrawalSig = await program.rpc.withdrawFromAccount(new anchor.BN(LAMPORTS_PER_SOL * 0.75), {
+      accounts: {
+        userAccount: userAccountPDA,
+        accountVault: accountVaultPDA,
+        accountOwner: userKP.publicKey,
+        config: configPDA,
+        systemProgram: SystemProgram.programId
+      },
+      signers: [userKP]
+    })
+
+    const [withdrawalEvent] = await eventsEmittedBy(withdrawalSig);
+    assert.equal(withdrawalEvent.name, "Withdrawal");
+    assert.equal(withdrawalEvent.data.amount.toNumber(), LAMPORTS_PER_SOL * 0.75);
+    assert.equal(withdrawalEvent.data.balance.toNumber(), 0);
+
+    const userAccountAfterWithdrawal = await program.account.userAccount.fetch(userAccountPDA);
+    assert.equal(userAccountAfterWithdrawal.currentBalance.toNumber(), 0);
+    assert.equal(await program.provider.connection.getBalance(accountVaultPDA), vaultRent);
+
+  });
+
+
+  // Placing Wagers //
+
+  // WILL NEED TO BE FULLY TESTED ON FRONT END
+  // Create a utility function to find bettor detail structs
+  it.skip('Cannot place multiple wagers on one bet state from the same betting user', async() => {
+
+    const betCreatorKeyPair = anchor.web3.Keypair.generate();
+    const sig = await program.provider.connection.requestAirdrop(betCreatorKeyPair.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(sig, "finalized");
+
+
+
+
+     ///// ***** INITIALIZE BET FUNCTIONALITY ***** /////
+
+    // Generate a new random keypair for betState
+    const betStateKP = anchor.web3.Keypair.generate();
+    const tradingPair = TICKERS[0];
+
+    const start = new anchor.BN(Date.now());
+    const duration = new anchor.BN(5 * 60 * 1000);
+
+    console.log("Starting the 'initialize bet state' functionality...");
+
+    await program.rpc.initializeBetState(
+      start,
+      duration,
+      tradingPair,
+      {
+        accounts: {
+          betState: betStateKP.publicKey,
+          betCreator: providerWallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId
+        },
+        signers:[betStateKP]
+      },
+    )
+
+    let betStateAccount = await program.account.betState.fetch(betStateKP.publicKey);
+
+    console.log(`${JSON.stringify(betStateAccount)}`);
+
+    assert.ok(betStateAccount);
+    assert.ok(betStateAccount.staticTotalPool.eq(new anchor.BN(0)));
+    assert.ok(betStateAccount.runningTotalPool.eq(new anchor.BN(0)));
+    assert.ok(betStateAccount.partyOnePool.eq(new anchor.BN(0)));
+    assert.ok(betStateAccount.partyTwoPool.eq(new anchor.BN(0)));
+    assert.ok(betStateAccount.status.hasOwnProperty("open"));
+    assert.ok(betStateAccount.betOutcome.hasOwnProperty("undecided"));
+
+
+
+            ///// USER 1 /////
+
+
+
+
+    ///// ***** USER 1 CREATES ACCOUNT ***** /////
+
+    const user1KP = anchor.web3.Keypair.generate();
+    const bettor1_airdrop_sig = await program.provider.connection.requestAirdrop(user1KP.publicKey, 2000000000)
+    await program.provider.connection.confirmTransaction(bettor1_airdrop_sig, "finalized");
+
+    const user1AccountKP = anchor.web3.Keypair.generate();
+
+    console.log("Initializaing User 1 Account...");
+
+    await program.rpc.initializeUserAccount({
+      accounts: {
+        userAccount: user1AccountKP.publicKey,
+        accountOwner: user1KP.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId
+      },
+      signers: [user1KP, user1AccountKP]
+    })
+
+    const userAccount = await program.account.userAccount.fetch(user1AccountKP.publicKey);
+
+    assert.ok(userAccount);
+    console.log(`Bettor 1 User Account: ${JSON.stringify(userAccount)}`);
+    assert.equal(userAccount.currentBalance.toNumber(), 0)
+    assert.equal(userAccount.wins.toNumber(), 0)
+    assert.equal(userAccount.losses.toNumber(), 0)
+
+
+
+
+    ///// ***** USER 1 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
+
+    const user1_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
+
+    console.log("User 1 Depositing...");
+
+    await program.rpc.depositIntoAccount(user1_lamports_to_deposit, {
       accounts: {
         userAccount: user1AccountKP.publicKey,
-        accountOwner: user1KP.publicKey
+        accountOwner: user1KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user1KP.publicKey,
-          lamports: user1_lamports_to_deposit_num,
-          toPubkey: user1AccountKP.publicKey
-        })
-      ],
       signers:[user1AccountKP, user1KP]
     })
 
@@ -1048,7 +2299,6 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 1 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
 
-    const user1_lamports_to_deposit_num = LAMPORTS_PER_SOL * 1
     const user1_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("User 1 Depositing...");
@@ -1056,15 +2306,10 @@ describe('juicy-bets', () => {
     await program.rpc.depositIntoAccount(user1_lamports_to_deposit, {
       accounts: {
         userAccount: user1AccountKP.publicKey,
-        accountOwner: user1KP.publicKey
+        accountOwner: user1KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user1KP.publicKey,
-          lamports: user1_lamports_to_deposit_num,
-          toPubkey: user1AccountKP.publicKey
-        })
-      ],
       signers:[user1AccountKP, user1KP]
     })
 
@@ -1166,7 +2411,6 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 2 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
 
-    const user2_lamports_deposit_num = LAMPORTS_PER_SOL * 1
     const user2_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("User 2 Depositing...");
@@ -1174,15 +2418,10 @@ describe('juicy-bets', () => {
     await program.rpc.depositIntoAccount(user2_lamports_to_deposit, {
       accounts: {
         userAccount: user2AccountKP.publicKey,
-        accountOwner: user2KP.publicKey
+        accountOwner: user2KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user2KP.publicKey,
-          lamports: user2_lamports_deposit_num,
-          toPubkey: user2AccountKP.publicKey
-        })
-      ],
       signers:[user2AccountKP, user2KP]
     })
 
@@ -1283,7 +2522,6 @@ describe('juicy-bets', () => {
 
     ///// ***** USER 3 DEPOSITS LAMPORTS INTO USER 1 ACCOUNT ***** /////
 
-    const user3_lamports_deposit_num = LAMPORTS_PER_SOL * 1
     const user3_lamports_to_deposit = new anchor.BN(LAMPORTS_PER_SOL * 1);
 
     console.log("User 3 Depositing...");
@@ -1291,15 +2529,10 @@ describe('juicy-bets', () => {
     await program.rpc.depositIntoAccount(user3_lamports_to_deposit, {
       accounts: {
         userAccount: user3AccountKP.publicKey,
-        accountOwner: user3KP.publicKey
+        accountOwner: user3KP.publicKey,
+        config: configPDA,
+        systemProgram: anchor.web3.SystemProgram.programId
       },
-      preInstructions: [
-        await SystemProgram.transfer({
-          fromPubkey: user3KP.publicKey,
-          lamports: user3_lamports_deposit_num,
-          toPubkey: user3AccountKP.publicKey
-        })
-      ],
       signers:[user3AccountKP, user3KP]
     })
 
@@ -1569,12 +2802,12 @@ describe('juicy-bets', () => {
 
     try {
       await program.rpc.claimWinnings(
-        bettor1Winnings,
         {
           accounts: {
             betState: betStateKeyPair.publicKey,
             wagerDetail: wagerDetail1.publicKey,
-            bettor: betCreatorKeyPair.publicKey
+            bettor: betCreatorKeyPair.publicKey,
+            config: configPDA
           },
           signers:[betStateKeyPair, wagerDetail1, betCreatorKeyPair]
         }
@@ -1731,12 +2964,12 @@ describe('juicy-bets', () => {
 
     try {
       await program.rpc.claimWinnings(
-        bettor1Winnings,
         {
           accounts: {
             betState: betStateKeyPair.publicKey,
             wagerDetail: wagerDetail1.publicKey,
-            bettor: betCreatorKeyPair.publicKey
+            bettor: betCreatorKeyPair.publicKey,
+            config: configPDA
           },
           signers:[betStateKeyPair, wagerDetail1, betCreatorKeyPair]
         }